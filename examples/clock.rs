@@ -1,4 +1,4 @@
-use std::fs;
+use std::fs::File;
 use nalgebra::{Affine3, Point3, Rotation3, Scale3, Translation3, Vector3};
 use gabes_graphics::{
     canvas::Canvas,
@@ -42,8 +42,8 @@ fn main() {
         canvas.set_pixel(x, y, color)
     }
 
-    // write canvas to ppm file
-    let ppm = canvas.to_ppm();
-    fs::write("examples/images/clock.ppm", ppm).expect("could not write to file");
+    // stream canvas to ppm file
+    let file = File::create("examples/images/clock.ppm").expect("could not create file");
+    canvas.write_ppm(file).expect("could not write to file");
 }
 
@@ -1,4 +1,4 @@
-use std::fs;
+use std::fs::File;
 use nalgebra as na;
 use gabes_graphics::{
     canvas::Canvas,
@@ -94,9 +94,9 @@ fn main() {
         p = tick(&e, p);
     }
 
-    // Write ppm formatted data to string then to file.
-    let ppm = c.to_ppm();
-    fs::write("examples/images/path.ppm", ppm).expect("could not write to file");
+    // Stream ppm formatted data straight to the file.
+    let file = File::create("examples/images/path.ppm").expect("could not create file");
+    c.write_ppm(file).expect("could not write to file");
 }
 
 
@@ -0,0 +1,68 @@
+use std::f32::consts::FRAC_PI_3;
+use std::fs::File;
+use nalgebra::{Affine3, Point3, Scale3, Translation3, Vector3};
+use gabes_graphics::{
+    camera::Camera,
+    canvas::color::Color,
+    light::PointLight,
+    material::Material,
+    math::affine::Affine3Ext,
+    math::view_transform,
+    shape::{plane::Plane, sphere::Sphere, Shape},
+    world::World,
+};
+
+fn main() {
+    let mut world = World::new();
+
+    let mut floor = Plane::new();
+    floor.set_material(Material {
+        color: Color::new(1.0, 0.9, 0.9),
+        specular: 0.0,
+        ..Material::default()
+    });
+    world.add_object(floor);
+
+    let mut middle = Sphere::new();
+    middle.set_transform(Translation3::new(-0.5, 1.0, 0.5));
+    middle.set_material(Material {
+        color: Color::new(0.1, 1.0, 0.5),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Material::default()
+    });
+    world.add_object(middle);
+
+    let mut right = Sphere::new();
+    right.set_transform(Translation3::new(1.5, 0.5, -0.5) * Affine3::from_scale(Scale3::new(0.5, 0.5, 0.5)));
+    right.set_material(Material {
+        color: Color::new(0.5, 1.0, 0.1),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Material::default()
+    });
+    world.add_object(right);
+
+    let mut left = Sphere::new();
+    left.set_transform(Translation3::new(-1.5, 0.33, -0.75) * Affine3::from_scale(Scale3::new(0.33, 0.33, 0.33)));
+    left.set_material(Material {
+        color: Color::new(1.0, 0.8, 0.1),
+        diffuse: 0.7,
+        specular: 0.3,
+        ..Material::default()
+    });
+    world.add_object(left);
+
+    world.add_light(PointLight::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+
+    let mut camera = Camera::new(200, 100, FRAC_PI_3);
+    camera.set_transform(view_transform(
+        &Point3::new(0.0, 1.5, -5.0),
+        &Point3::new(0.0, 1.0, 0.0),
+        &Vector3::new(0.0, 1.0, 0.0),
+    ));
+
+    // render straight into a canvas, then stream it to a ppm file
+    let file = File::create("examples/images/spheres.ppm").expect("could not create file");
+    camera.render(&world).write_ppm(file).expect("could not write to file");
+}
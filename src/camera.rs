@@ -0,0 +1,189 @@
+use nalgebra::{Affine3, Point3};
+use crate::canvas::Canvas;
+use crate::math::affine::ToAffine3;
+use crate::math::ray::Ray;
+use crate::world::World;
+
+/// A pinhole camera, mapping the pixels of a canvas onto rays into the world.
+///
+/// The canvas sits one unit in front of the camera. `transform` orients the world relative
+/// to the camera, usually built with `math::view_transform`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera {
+    hsize: usize,
+    vsize: usize,
+    field_of_view: f32,
+    transform: Affine3<f32>,
+    inverse: Affine3<f32>,
+    half_width: f32,
+    half_height: f32,
+    pixel_size: f32,
+}
+
+impl Camera {
+    /// `hsize` by `vsize` pixels, with `field_of_view` in radians across the wider side
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f32) -> Self {
+        // Width of half the canvas, one unit away from the camera
+        let half_view = (field_of_view / 2.0).tan();
+        let aspect = hsize as f32 / vsize as f32;
+        let (half_width, half_height) = if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        };
+
+        Self {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Affine3::identity(),
+            inverse: Affine3::identity(),
+            half_width,
+            half_height,
+            pixel_size: (half_width * 2.0) / hsize as f32,
+        }
+    }
+
+    pub fn hsize(&self) -> usize {
+        self.hsize
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.vsize
+    }
+
+    pub fn field_of_view(&self) -> f32 {
+        self.field_of_view
+    }
+
+    /// The size of a pixel on the canvas, in world units
+    pub fn pixel_size(&self) -> f32 {
+        self.pixel_size
+    }
+
+    pub fn transform(&self) -> &Affine3<f32> {
+        &self.transform
+    }
+
+    /// Place the camera in the world.
+    ///
+    /// # Panics
+    ///
+    /// If the transform can't be inverted, like a scale of zero along some axis.
+    pub fn set_transform<M: ToAffine3<f32>>(&mut self, transform: M) {
+        let transform = transform.to_affine3();
+        self.inverse = transform
+            .try_inverse()
+            .expect("a camera's transform must be invertible, it can't scale any axis to zero");
+        self.transform = transform;
+    }
+
+    /// The ray from the camera through the center of pixel (x, y)
+    ///
+    /// (0, 0) is the "top left", like on the `Canvas`
+    pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
+        // Offset from the edge of the canvas to the pixel's center
+        let xoffset = (x as f32 + 0.5) * self.pixel_size;
+        let yoffset = (y as f32 + 0.5) * self.pixel_size;
+
+        // The camera looks toward -z, so +x is to the *left*
+        let world_x = self.half_width - xoffset;
+        let world_y = self.half_height - yoffset;
+
+        let pixel = self.inverse * Point3::new(world_x, world_y, -1.0);
+        let origin = self.inverse * Point3::origin();
+        Ray::new(origin, (pixel - origin).normalize())
+    }
+
+    /// Render the world, one ray per pixel
+    pub fn render(&self, world: &World) -> Canvas {
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel(x, y);
+                image.set_pixel(x, y, world.color_at(&ray));
+            }
+        }
+        image
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_1_SQRT_2, FRAC_PI_2, FRAC_PI_4};
+    use approx::assert_relative_eq;
+    use nalgebra::{Rotation3, Scale3, Translation3, Vector3};
+    use crate::canvas::color::Color;
+    use crate::math::view_transform;
+    use crate::world::test_world;
+
+    #[test]
+    fn new() {
+        let c = Camera::new(160, 120, FRAC_PI_2);
+        assert_eq!(c.hsize(), 160);
+        assert_eq!(c.vsize(), 120);
+        assert_relative_eq!(c.field_of_view(), FRAC_PI_2);
+        assert_relative_eq!(c.transform().matrix(), Affine3::identity().matrix());
+    }
+
+    #[test]
+    fn pixel_size_horizontal() {
+        let c = Camera::new(200, 125, FRAC_PI_2);
+        assert_relative_eq!(c.pixel_size(), 0.01, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn pixel_size_vertical() {
+        let c = Camera::new(125, 200, FRAC_PI_2);
+        assert_relative_eq!(c.pixel_size(), 0.01, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn ray_through_center() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(100, 50);
+        assert_relative_eq!(r.origin, Point3::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(r.direction, Vector3::new(0.0, 0.0, -1.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn ray_through_corner() {
+        let c = Camera::new(201, 101, FRAC_PI_2);
+        let r = c.ray_for_pixel(0, 0);
+        assert_relative_eq!(r.origin, Point3::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(r.direction, Vector3::new(0.66519, 0.33259, -0.66851), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn ray_transformed_camera() {
+        let mut c = Camera::new(201, 101, FRAC_PI_2);
+        c.set_transform(
+            Rotation3::from_axis_angle(&Vector3::y_axis(), FRAC_PI_4) * Translation3::new(0.0, -2.0, 5.0)
+        );
+        let r = c.ray_for_pixel(100, 50);
+        assert_relative_eq!(r.origin, Point3::new(0.0, 2.0, -5.0), epsilon = 1e-5);
+        assert_relative_eq!(r.direction, Vector3::new(FRAC_1_SQRT_2, 0.0, -FRAC_1_SQRT_2), epsilon = 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be invertible")]
+    fn singular_transform() {
+        let mut c = Camera::new(201, 101, FRAC_PI_2);
+        c.set_transform(Scale3::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn render() {
+        let w = test_world();
+        let mut c = Camera::new(11, 11, FRAC_PI_2);
+        let from = Point3::new(0.0, 0.0, -5.0);
+        let to = Point3::new(0.0, 0.0, 0.0);
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        c.set_transform(view_transform(&from, &to, &up));
+        let image = c.render(&w);
+        assert_eq!(image.width(), 11);
+        assert_eq!(image.height(), 11);
+        assert_relative_eq!(image.get_pixel(5, 5), Color::new(0.38066, 0.47583, 0.2855), epsilon = 1e-4);
+    }
+}
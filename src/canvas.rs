@@ -1,7 +1,16 @@
 pub mod color;
+pub mod png;
+pub mod ppm;
 
+use std::io;
 use color::Color;
+use ppm::PpmOptions;
 
+/// A grid of pixels to render into.
+///
+/// The `write_*` methods stream an image file to any `std::io::Write` and buffer their writes,
+/// there is no need to wrap a file in a `BufWriter` first.
+#[derive(Debug, Clone)]
 pub struct Canvas {
     width: usize,
     height: usize,
@@ -18,6 +27,14 @@ impl Canvas {
         }
     }
 
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
     /// Draw a color at the given coordinate
     ///
     /// (0, 0) is the "top left"
@@ -28,25 +45,41 @@ impl Canvas {
     }
 
     /// Get the color at the given coordinate
-    fn get_pixel(&self, x: usize, y: usize) -> Color {
+    pub fn get_pixel(&self, x: usize, y: usize) -> Color {
         assert!(x < self.width && y < self.height);
 
         self.pixels[x + y * self.width]
     }
 
-    /// Write the contents of the canvas to a PPM file
+    /// Write the contents of the canvas to a PPM file, one pixel per line
     pub fn to_ppm(&self) -> String {
-        let max_color_value = 255;
-        let header = format!("P3\n{} {}\n{}\n", self.width, self.height, max_color_value);
-        let mut data = String::from(header);
-        for i in 0..self.height {
-            for j in 0..self.width {
-                let c = self.get_pixel(j, i);
-                let (r, g, b) = c.scale(max_color_value);
-                data.push_str(&format!("{r} {g} {b}\n"));
-            }
-        }
-        data
+        self.to_ppm_with(&PpmOptions::default())
+    }
+}
+
+/// Run one of the streaming writers into memory.
+///
+/// Writing to a `Vec` can't fail, so the only errors left are invalid options, which panic here.
+fn write_to_vec(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> Vec<u8> {
+    let mut data = Vec::new();
+    if let Err(err) = write(&mut data) {
+        panic!("{err}");
+    }
+    data
+}
+
+/// A writer that is always full, to check that write errors are passed on
+#[cfg(test)]
+pub(crate) struct FullWriter;
+
+#[cfg(test)]
+impl io::Write for FullWriter {
+    fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+        Err(io::Error::new(io::ErrorKind::StorageFull, "full"))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
     }
 }
 
@@ -103,5 +136,7 @@ mod tests {
         let canvas = Canvas::new(1920, 1080);
         let ppm = canvas.to_ppm();
         assert_eq!(ppm.chars().last().unwrap(), '\n');
+        let ppm = canvas.to_ppm_with(&PpmOptions::wrapped());
+        assert_eq!(ppm.chars().last().unwrap(), '\n');
     }
 }
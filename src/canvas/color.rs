@@ -29,13 +29,13 @@ impl Color {
     /// Scale each rgb component by max_color_value.
     /// Then clamp each component between 0..=max_color_value.
     /// Then return each component rounded to integers.
-    pub(crate) fn scale(&self, max_color_value: u8) -> (u8, u8, u8) {
+    pub(crate) fn scale(&self, max_color_value: u16) -> (u16, u16, u16) {
         let max_color_value = max_color_value as f32;
-        let rgb: na::Vector3<u8> = self
+        let rgb: na::Vector3<u16> = self
             .rgb
             .map(|x| (x * max_color_value)
                 .clamp(0.0, max_color_value)
-                .round() as u8);
+                .round() as u16);
         (rgb.x, rgb.y, rgb.z)
     }
 }
@@ -143,4 +143,12 @@ mod tests {
         let c2 = Color::new(0.9, 1.0, 0.1);
         assert_relative_eq!(c1 * c2, Color::new(0.9, 0.2, 0.04));
     }
+
+    #[test]
+    fn scale() {
+        let c = Color::new(1.5, 0.5, -0.5);
+        assert_eq!(c.scale(255), (255, 128, 0));
+        assert_eq!(c.scale(65535), (65535, 32768, 0));
+        assert_eq!(Color::new(0.2, 0.4, 0.6).scale(1), (0, 0, 1));
+    }
 }
@@ -0,0 +1,754 @@
+mod zlib;
+
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+use std::mem;
+use crate::canvas::color::Color;
+use crate::canvas::{write_to_vec, Canvas};
+
+/// Every PNG file starts with these bytes
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+/// PNG dimensions and chunk lengths are limited to 31 bits
+const MAX_SIZE: usize = i32::MAX as usize;
+/// How much compressed image data goes in each IDAT chunk
+const IDAT_SIZE: usize = 1 << 16;
+/// CRC-32 lookup table for every possible byte, built at compile time
+const CRC_TABLE: [u32; 256] = crc_table();
+
+/// How many bits each sample of a PNG file takes
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PngBitDepth {
+    #[default]
+    Eight,
+    Sixteen,
+}
+
+/// Which channels a PNG file stores
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PngColorType {
+    #[default]
+    Rgb,
+    /// RGB with an alpha channel. Canvases are opaque, so alpha is always written as fully opaque.
+    Rgba,
+}
+
+/// How `Canvas::write_png_with` compresses the image data
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PngCompression {
+    /// Not compressed at all, the fastest to write
+    Stored,
+    /// LZ77 matching with deflate's fixed Huffman codes
+    #[default]
+    FixedHuffman,
+}
+
+/// Settings for writing PNG files. The default is 8-bit RGB, compressed.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct PngOptions {
+    pub bit_depth: PngBitDepth,
+    pub color_type: PngColorType,
+    pub compression: PngCompression,
+}
+
+impl PngOptions {
+    fn max_sample(&self) -> u16 {
+        match self.bit_depth {
+            PngBitDepth::Eight => u8::MAX as u16,
+            PngBitDepth::Sixteen => u16::MAX,
+        }
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+        let channels = match self.color_type {
+            PngColorType::Rgb => 3,
+            PngColorType::Rgba => 4,
+        };
+        match self.bit_depth {
+            PngBitDepth::Eight => channels,
+            PngBitDepth::Sixteen => 2 * channels,
+        }
+    }
+}
+
+impl Canvas {
+    /// Write the contents of the canvas to an 8-bit RGB PNG file
+    pub fn to_png(&self) -> Vec<u8> {
+        self.to_png_with(&PngOptions::default())
+    }
+
+    /// Write the contents of the canvas to a PNG file, encoded as `options` says
+    ///
+    /// # Panics
+    ///
+    /// If the canvas is empty, or too large for a PNG file.
+    pub fn to_png_with(&self, options: &PngOptions) -> Vec<u8> {
+        write_to_vec(|data| self.write_png_with(data, options))
+    }
+
+    /// Stream the canvas to `writer` as an 8-bit RGB PNG file
+    pub fn write_png<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_png_with(writer, &PngOptions::default())
+    }
+
+    /// Stream the canvas to `writer` as a PNG file, encoded as `options` says.
+    ///
+    /// PNG has no empty images, and none wider or higher than 2^31 - 1 pixels. Those canvases are an
+    /// `InvalidInput` error.
+    pub fn write_png_with<W: Write>(&self, writer: W, options: &PngOptions) -> io::Result<()> {
+        if self.width > MAX_SIZE || self.height > MAX_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("PNG images are at most {MAX_SIZE} pixels wide and high"),
+            ));
+        }
+        if self.width == 0 || self.height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "PNG images can't be empty"));
+        }
+
+        let image_data = zlib::compress(&self.png_scanlines(options), options.compression);
+
+        let mut w = BufWriter::new(writer);
+        w.write_all(&SIGNATURE)?;
+
+        let mut header = Vec::with_capacity(13);
+        header.extend_from_slice(&(self.width as u32).to_be_bytes());
+        header.extend_from_slice(&(self.height as u32).to_be_bytes());
+        header.push(match options.bit_depth {
+            PngBitDepth::Eight => 8,
+            PngBitDepth::Sixteen => 16,
+        });
+        header.push(match options.color_type {
+            PngColorType::Rgb => 2,
+            PngColorType::Rgba => 6,
+        });
+        // Deflate compression, adaptive filtering, not interlaced
+        header.extend_from_slice(&[0, 0, 0]);
+        write_chunk(&mut w, b"IHDR", &header)?;
+
+        for data in image_data.chunks(IDAT_SIZE) {
+            write_chunk(&mut w, b"IDAT", data)?;
+        }
+        write_chunk(&mut w, b"IEND", &[])?;
+        w.flush()
+    }
+
+    /// Load an 8- or 16-bit RGB or RGBA PNG image. Alpha is dropped, canvases are opaque.
+    pub fn from_png(png: &[u8]) -> Result<Canvas, PngError> {
+        parse(png)
+    }
+
+    /// Load a PNG image like `from_png` does, from a file or anything else readable
+    pub fn read_png<R: Read>(mut reader: R) -> Result<Canvas, PngError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        parse(&bytes)
+    }
+
+    /// The image data before compression, every row of samples behind the filter that shrinks it the most
+    fn png_scanlines(&self, options: &PngOptions) -> Vec<u8> {
+        let max_sample = options.max_sample();
+        let bpp = options.bytes_per_pixel();
+        let stride = self.width * bpp;
+
+        let mut data = Vec::with_capacity((stride + 1) * self.height);
+        let mut previous = vec![0; stride];
+        let mut current = Vec::with_capacity(stride);
+        let mut filtered = vec![0; stride];
+        let mut best = vec![0; stride];
+        for row in self.pixels.chunks(self.width) {
+            current.clear();
+            for pixel in row {
+                let (r, g, b) = pixel.scale(max_sample);
+                let alpha = (options.color_type == PngColorType::Rgba).then_some(max_sample);
+                for sample in [r, g, b].into_iter().chain(alpha) {
+                    match options.bit_depth {
+                        PngBitDepth::Eight => current.push(sample as u8),
+                        PngBitDepth::Sixteen => current.extend_from_slice(&sample.to_be_bytes()),
+                    }
+                }
+            }
+
+            // The usual heuristic: the filter whose output is closest to zero, read as signed bytes
+            let mut best_filter = Filter::None;
+            let mut best_cost = usize::MAX;
+            for filter in Filter::ALL {
+                filter.apply(&current, &previous, bpp, &mut filtered);
+                let cost = filtered.iter().map(|&byte| (byte as i8).unsigned_abs() as usize).sum();
+                if cost < best_cost {
+                    best_cost = cost;
+                    best_filter = filter;
+                    mem::swap(&mut best, &mut filtered);
+                }
+            }
+            data.push(best_filter as u8);
+            data.extend_from_slice(&best);
+            mem::swap(&mut previous, &mut current);
+        }
+        data
+    }
+}
+
+fn write_chunk<W: Write>(w: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    w.write_all(&(data.len() as u32).to_be_bytes())?;
+    w.write_all(kind)?;
+    w.write_all(data)?;
+    w.write_all(&crc32(&[kind, data]).to_be_bytes())
+}
+
+/// Parse a whole PNG file.
+///
+/// The IHDR chunk comes first, then the image data split across any number of IDAT chunks, and IEND last.
+/// Other chunks, like gamma or text, are skipped.
+fn parse(bytes: &[u8]) -> Result<Canvas, PngError> {
+    let mut chunks = Chunks {
+        bytes: bytes.strip_prefix(&SIGNATURE).ok_or(PngError::NotPng)?,
+    };
+
+    let header = match chunks.next()? {
+        (b"IHDR", data) => Header::parse(data)?,
+        _ => return Err(PngError::Malformed("the first chunk isn't IHDR")),
+    };
+
+    let mut image_data = Vec::new();
+    loop {
+        match chunks.next()? {
+            (b"IDAT", data) => image_data.extend_from_slice(data),
+            (b"IEND", _) => break,
+            (b"IHDR", _) => return Err(PngError::Malformed("more than one IHDR chunk")),
+            // A suggested palette, truecolor images don't need it
+            (b"PLTE", _) => {}
+            // Chunks with an uppercase first letter are critical, the image can't be shown without them
+            (kind, _) if kind[0].is_ascii_uppercase() => {
+                return Err(PngError::Unsupported(format!(
+                    "critical chunk {}",
+                    String::from_utf8_lossy(kind)
+                )))
+            }
+            _ => {}
+        }
+    }
+    if image_data.is_empty() {
+        return Err(PngError::Malformed("no IDAT chunk"));
+    }
+
+    // Each row is a filter type byte followed by its samples
+    let bpp = header.bytes_per_pixel();
+    let (stride, size) = header
+        .width
+        .checked_mul(bpp)
+        .and_then(|stride| Some((stride, (stride + 1).checked_mul(header.height)?)))
+        .ok_or(PngError::Unsupported("images this large".to_string()))?;
+    let mut data = zlib::decompress(&image_data, size).map_err(PngError::Malformed)?;
+    if data.len() < size {
+        return Err(PngError::Malformed("not enough image data"));
+    }
+
+    let mut previous = vec![0; stride];
+    for row in data.chunks_exact_mut(stride + 1) {
+        let (filter, samples) = row.split_first_mut().expect("rows are at least a byte long");
+        let filter = Filter::from_byte(*filter).ok_or(PngError::Malformed("unknown filter type"))?;
+        filter.undo(samples, &previous, bpp);
+        previous.copy_from_slice(samples);
+    }
+
+    let mut canvas = Canvas::new(header.width, header.height);
+    let max_sample = match header.bit_depth {
+        PngBitDepth::Eight => u8::MAX as f32,
+        PngBitDepth::Sixteen => u16::MAX as f32,
+    };
+    let rows = data.chunks_exact(stride + 1).map(|row| &row[1..]);
+    for (pixel, samples) in canvas.pixels.iter_mut().zip(rows.flat_map(|row| row.chunks_exact(bpp))) {
+        let sample = |i: usize| match header.bit_depth {
+            PngBitDepth::Eight => samples[i] as f32,
+            PngBitDepth::Sixteen => u16::from_be_bytes([samples[2 * i], samples[2 * i + 1]]) as f32,
+        };
+        *pixel = Color::new(sample(0) / max_sample, sample(1) / max_sample, sample(2) / max_sample);
+    }
+    Ok(canvas)
+}
+
+/// What the IHDR chunk says about the image
+struct Header {
+    width: usize,
+    height: usize,
+    bit_depth: PngBitDepth,
+    color_type: PngColorType,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, PngError> {
+        let &[w0, w1, w2, w3, h0, h1, h2, h3, bit_depth, color_type, compression, filter, interlace] = data else {
+            return Err(PngError::Malformed("IHDR chunk has the wrong length"));
+        };
+
+        let width = u32::from_be_bytes([w0, w1, w2, w3]) as usize;
+        let height = u32::from_be_bytes([h0, h1, h2, h3]) as usize;
+        if !(1..=MAX_SIZE).contains(&width) || !(1..=MAX_SIZE).contains(&height) {
+            return Err(PngError::Malformed("image width or height out of range"));
+        }
+        let bit_depth = match bit_depth {
+            8 => PngBitDepth::Eight,
+            16 => PngBitDepth::Sixteen,
+            depth => return Err(PngError::Unsupported(format!("bit depth {depth}"))),
+        };
+        let color_type = match color_type {
+            2 => PngColorType::Rgb,
+            6 => PngColorType::Rgba,
+            0 => return Err(PngError::Unsupported("grayscale images".to_string())),
+            3 => return Err(PngError::Unsupported("palette images".to_string())),
+            4 => return Err(PngError::Unsupported("grayscale images with alpha".to_string())),
+            other => return Err(PngError::Unsupported(format!("color type {other}"))),
+        };
+        if compression != 0 || filter != 0 {
+            return Err(PngError::Unsupported("compression or filter methods other than 0".to_string()));
+        }
+        if interlace != 0 {
+            return Err(PngError::Unsupported("interlaced images".to_string()));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            bit_depth,
+            color_type,
+        })
+    }
+
+    fn bytes_per_pixel(&self) -> usize {
+        PngOptions {
+            bit_depth: self.bit_depth,
+            color_type: self.color_type,
+            ..PngOptions::default()
+        }
+        .bytes_per_pixel()
+    }
+}
+
+struct Chunks<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Chunks<'a> {
+    /// The type and data of the next chunk, once its CRC checks out
+    fn next(&mut self) -> Result<(&'a [u8; 4], &'a [u8]), PngError> {
+        let (length, rest) = self.bytes.split_first_chunk::<4>().ok_or(PngError::UnexpectedEof)?;
+        let length = u32::from_be_bytes(*length) as usize;
+        let (kind, rest) = rest.split_first_chunk::<4>().ok_or(PngError::UnexpectedEof)?;
+        if !kind.iter().all(u8::is_ascii_alphabetic) {
+            return Err(PngError::Malformed("invalid chunk type"));
+        }
+        if rest.len() < length.saturating_add(4) {
+            return Err(PngError::UnexpectedEof);
+        }
+        let (data, rest) = rest.split_at(length);
+        let (crc, rest) = rest.split_first_chunk::<4>().ok_or(PngError::UnexpectedEof)?;
+        if u32::from_be_bytes(*crc) != crc32(&[kind, data]) {
+            return Err(PngError::CrcMismatch(String::from_utf8_lossy(kind).into_owned()));
+        }
+        self.bytes = rest;
+        Ok((kind, data))
+    }
+}
+
+/// The filter in front of each row of image data, predicting every byte from its neighbours to the left and above
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Filter {
+    None = 0,
+    Sub = 1,
+    Up = 2,
+    Average = 3,
+    Paeth = 4,
+}
+
+impl Filter {
+    const ALL: [Filter; 5] = [Filter::None, Filter::Sub, Filter::Up, Filter::Average, Filter::Paeth];
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL.get(byte as usize).copied()
+    }
+
+    /// The prediction for a byte, from the bytes left of it (`a`), above it (`b`) and above and left (`c`)
+    fn predict(self, a: u8, b: u8, c: u8) -> u8 {
+        match self {
+            Filter::None => 0,
+            Filter::Sub => a,
+            Filter::Up => b,
+            Filter::Average => ((a as u16 + b as u16) / 2) as u8,
+            Filter::Paeth => {
+                let p = a as i16 + b as i16 - c as i16;
+                let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+                if pa <= pb && pa <= pc {
+                    a
+                } else if pb <= pc {
+                    b
+                } else {
+                    c
+                }
+            }
+        }
+    }
+
+    /// Filter the row `current`, which comes after `previous`, into `out`
+    fn apply(self, current: &[u8], previous: &[u8], bpp: usize, out: &mut [u8]) {
+        for i in 0..current.len() {
+            let (a, c) = if i >= bpp { (current[i - bpp], previous[i - bpp]) } else { (0, 0) };
+            out[i] = current[i].wrapping_sub(self.predict(a, previous[i], c));
+        }
+    }
+
+    /// Turn a filtered row back into samples in place
+    fn undo(self, row: &mut [u8], previous: &[u8], bpp: usize) {
+        for i in 0..row.len() {
+            let (a, c) = if i >= bpp { (row[i - bpp], previous[i - bpp]) } else { (0, 0) };
+            row[i] = row[i].wrapping_add(self.predict(a, previous[i], c));
+        }
+    }
+}
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+/// The CRC-32 of `parts` one after another, the checksum at the end of every chunk
+fn crc32(parts: &[&[u8]]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in parts.iter().copied().flatten() {
+        crc = CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    !crc
+}
+
+/// Why a PNG image could not be loaded
+#[derive(Debug)]
+pub enum PngError {
+    Io(io::Error),
+    /// The file doesn't start with the PNG signature
+    NotPng,
+    /// The file ends in the middle of a chunk, or before the IEND chunk
+    UnexpectedEof,
+    /// A chunk whose CRC doesn't match its contents
+    CrcMismatch(String),
+    /// A valid image this decoder can't read, like a palette or interlaced one
+    Unsupported(String),
+    /// Chunks or image data that break the PNG or zlib format
+    Malformed(&'static str),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::NotPng => write!(f, "not a PNG file"),
+            Self::UnexpectedEof => write!(f, "unexpected end of file"),
+            Self::CrcMismatch(kind) => write!(f, "CRC mismatch in {kind} chunk"),
+            Self::Unsupported(what) => write!(f, "unsupported PNG file: {what}"),
+            Self::Malformed(reason) => write!(f, "malformed PNG file: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for PngError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PngError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::FullWriter;
+    use approx::assert_relative_eq;
+
+    fn gradient(width: usize, height: usize) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let color = Color::new(x as f32 / width as f32, y as f32 / height as f32, 0.123_456);
+                canvas.set_pixel(x, y, color);
+            }
+        }
+        canvas
+    }
+
+    /// Put together a PNG file from raw chunks
+    fn build_png(chunks: &[(&[u8; 4], &[u8])]) -> Vec<u8> {
+        let mut png = SIGNATURE.to_vec();
+        for (kind, data) in chunks {
+            write_chunk(&mut png, kind, data).unwrap();
+        }
+        png
+    }
+
+    fn rgb_header(width: u32, height: u32) -> Vec<u8> {
+        let mut header = width.to_be_bytes().to_vec();
+        header.extend_from_slice(&height.to_be_bytes());
+        header.extend_from_slice(&[8, 2, 0, 0, 0]);
+        header
+    }
+
+    #[test]
+    fn crc32() {
+        assert_eq!(super::crc32(&[b"123456789"]), 0xcbf4_3926);
+        assert_eq!(super::crc32(&[b"IEND"]), 0xae42_6082);
+        assert_eq!(super::crc32(&[b"IE", b"", b"ND"]), 0xae42_6082);
+    }
+
+    #[test]
+    fn file_layout() {
+        let png = Canvas::new(3, 2).to_png();
+        assert_eq!(png[..8], SIGNATURE);
+        // IHDR, 13 bytes long
+        assert_eq!(png[8..16], [0, 0, 0, 13, b'I', b'H', b'D', b'R']);
+        assert_eq!(png[16..29], [0, 0, 0, 3, 0, 0, 0, 2, 8, 2, 0, 0, 0]);
+        assert_eq!(png[37..41], *b"IDAT");
+        // An empty IEND chunk
+        assert_eq!(png[png.len() - 12..], [0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]);
+
+        let options = PngOptions {
+            bit_depth: PngBitDepth::Sixteen,
+            color_type: PngColorType::Rgba,
+            ..PngOptions::default()
+        };
+        let png = Canvas::new(3, 2).to_png_with(&options);
+        assert_eq!(png[24..26], [16, 6]);
+    }
+
+    #[test]
+    fn round_trip() {
+        let canvas = gradient(37, 23);
+        for bit_depth in [PngBitDepth::Eight, PngBitDepth::Sixteen] {
+            for color_type in [PngColorType::Rgb, PngColorType::Rgba] {
+                for compression in [PngCompression::Stored, PngCompression::FixedHuffman] {
+                    let options = PngOptions {
+                        bit_depth,
+                        color_type,
+                        compression,
+                    };
+                    let epsilon = 0.5 / options.max_sample() as f32;
+                    let loaded = Canvas::from_png(&canvas.to_png_with(&options)).unwrap();
+                    assert_eq!((loaded.width(), loaded.height()), (37, 23));
+                    for y in 0..23 {
+                        for x in 0..37 {
+                            assert_relative_eq!(loaded.get_pixel(x, y), canvas.get_pixel(x, y), epsilon = epsilon);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_colors_are_clamped() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set_pixel(0, 0, Color::new(1.5, -0.5, 0.5));
+        let loaded = Canvas::from_png(&canvas.to_png()).unwrap();
+        assert_relative_eq!(loaded.get_pixel(0, 0), Color::new(1.0, 0.0, 0.50196), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn compression_shrinks_files() {
+        let canvas = gradient(100, 100);
+        let stored = canvas.to_png_with(&PngOptions {
+            compression: PngCompression::Stored,
+            ..PngOptions::default()
+        });
+        assert!(stored.len() > 100 * 100 * 3);
+        assert!(canvas.to_png().len() < stored.len() / 2);
+    }
+
+    #[test]
+    fn write_matches_to_png() {
+        let canvas = gradient(20, 5);
+        let mut written = Vec::new();
+        canvas.write_png(&mut written).unwrap();
+        assert_eq!(written, canvas.to_png());
+
+        let loaded = Canvas::read_png(written.as_slice()).unwrap();
+        assert_relative_eq!(loaded.get_pixel(19, 4), canvas.get_pixel(19, 4), epsilon = 1.0 / 255.0);
+    }
+
+    #[test]
+    fn empty_canvas_is_an_error() {
+        for canvas in [Canvas::new(0, 0), Canvas::new(3, 0), Canvas::new(0, 3)] {
+            let err = canvas.write_png(Vec::new()).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        }
+    }
+
+    #[test]
+    fn too_wide_canvas_is_an_error() {
+        // No pixels to allocate, but still wider than PNG allows
+        let err = Canvas::new(MAX_SIZE + 1, 0).write_png(Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("at most"));
+    }
+
+    #[test]
+    fn write_errors_are_passed_on() {
+        assert_eq!(Canvas::new(2, 2).write_png(FullWriter).unwrap_err().kind(), io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn read_other_encoder() {
+        // A 2x2 image written by hand with Python's zlib, the first row Sub filtered and the second Up filtered
+        let png = [
+            0x89, 0x50, 0x4e, 0x47, 0x0d, 0x0a, 0x1a, 0x0a, 0x00, 0x00, 0x00, 0x0d, 0x49, 0x48, 0x44, 0x52, 0x00, 0x00,
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x02, 0x08, 0x02, 0x00, 0x00, 0x00, 0xfd, 0xd4, 0x9a, 0x73, 0x00, 0x00, 0x00,
+            0x16, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9c, 0x63, 0xfc, 0xcf, 0xc0, 0xc0, 0xf8, 0x9f, 0x81, 0x89, 0x91, 0xe1,
+            0xff, 0x7f, 0x86, 0xff, 0x00, 0x1e, 0x1c, 0x05, 0x01, 0x3b, 0x46, 0x0b, 0x07, 0x00, 0x00, 0x00, 0x00, 0x49,
+            0x45, 0x4e, 0x44, 0xae, 0x42, 0x60, 0x82,
+        ];
+        let canvas = Canvas::from_png(&png).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(canvas.get_pixel(1, 0), Color::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(canvas.get_pixel(0, 1), Color::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(canvas.get_pixel(1, 1), Color::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn alpha_is_dropped() {
+        let mut header = rgb_header(1, 1);
+        header[9] = 6;
+        let data = zlib::compress(&[0, 255, 51, 0, 0], PngCompression::Stored);
+        let png = build_png(&[(b"IHDR", &header), (b"IDAT", &data), (b"IEND", &[])]);
+        let canvas = Canvas::from_png(&png).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.2, 0.0));
+    }
+
+    #[test]
+    fn ancillary_chunks_and_split_image_data() {
+        let data = zlib::compress(&[0, 255, 51, 0, 0, 0, 102, 255], PngCompression::FixedHuffman);
+        let (first, second) = data.split_at(5);
+        let png = build_png(&[
+            (b"IHDR", &rgb_header(1, 2)),
+            (b"gAMA", &[0, 0, 0xb1, 0x8f]),
+            (b"IDAT", first),
+            (b"tEXt", b"Comment\0rendered by Gabes Graphics"),
+            (b"IDAT", second),
+            (b"IEND", &[]),
+        ]);
+        let canvas = Canvas::from_png(&png).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.2, 0.0));
+        assert_relative_eq!(canvas.get_pixel(0, 1), Color::new(0.0, 0.4, 1.0));
+    }
+
+    #[test]
+    fn filters_undo_themselves() {
+        let previous = [10, 200, 30, 40, 250, 60, 7, 0];
+        let current = [0, 255, 128, 1, 90, 90, 91, 3];
+        for filter in Filter::ALL {
+            let mut filtered = [0; 8];
+            filter.apply(&current, &previous, 3, &mut filtered);
+            filter.undo(&mut filtered, &previous, 3);
+            assert_eq!(filtered, current, "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn paeth_predictor() {
+        assert_eq!(Filter::Paeth.predict(10, 20, 10), 20);
+        assert_eq!(Filter::Paeth.predict(20, 10, 10), 20);
+        assert_eq!(Filter::Paeth.predict(10, 20, 30), 10);
+        assert_eq!(Filter::Paeth.predict(100, 100, 100), 100);
+    }
+
+    #[test]
+    fn not_png() {
+        assert!(matches!(Canvas::from_png(b"P6\n1 1\n255\n"), Err(PngError::NotPng)));
+        assert!(matches!(Canvas::from_png(b""), Err(PngError::NotPng)));
+    }
+
+    #[test]
+    fn truncated() {
+        let png = gradient(10, 10).to_png();
+        for length in [8, 20, png.len() - 12, png.len() - 1] {
+            assert!(matches!(Canvas::from_png(&png[..length]), Err(PngError::UnexpectedEof)));
+        }
+    }
+
+    #[test]
+    fn crc_mismatch() {
+        let mut png = Canvas::new(2, 2).to_png();
+        png[20] ^= 1;
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::CrcMismatch(kind)) if kind == "IHDR"));
+    }
+
+    #[test]
+    fn unsupported_images() {
+        let mut header = rgb_header(1, 1);
+        header[12] = 1;
+        let png = build_png(&[(b"IHDR", &header), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::Unsupported(what)) if what == "interlaced images"));
+
+        let mut header = rgb_header(1, 1);
+        header[9] = 3;
+        let png = build_png(&[(b"IHDR", &header), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::Unsupported(what)) if what == "palette images"));
+
+        let mut header = rgb_header(1, 1);
+        header[8] = 4;
+        let png = build_png(&[(b"IHDR", &header), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::Unsupported(what)) if what == "bit depth 4"));
+    }
+
+    #[test]
+    fn malformed_images() {
+        let header = rgb_header(2, 2);
+        let missing_header = build_png(&[(b"IDAT", &[]), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&missing_header), Err(PngError::Malformed(_))));
+
+        let no_data = build_png(&[(b"IHDR", &header), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&no_data), Err(PngError::Malformed("no IDAT chunk"))));
+
+        let empty = build_png(&[(b"IHDR", &rgb_header(0, 2)), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&empty), Err(PngError::Malformed(_))));
+
+        let short = zlib::compress(&[0; 10], PngCompression::FixedHuffman);
+        let short = build_png(&[(b"IHDR", &header), (b"IDAT", &short), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&short), Err(PngError::Malformed("not enough image data"))));
+
+        let long = zlib::compress(&[0; 15], PngCompression::FixedHuffman);
+        let long = build_png(&[(b"IHDR", &header), (b"IDAT", &long), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&long), Err(PngError::Malformed(_))));
+
+        let bad_filter = zlib::compress(&[5, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], PngCompression::Stored);
+        let bad_filter = build_png(&[(b"IHDR", &header), (b"IDAT", &bad_filter), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&bad_filter), Err(PngError::Malformed("unknown filter type"))));
+    }
+
+    #[test]
+    fn huge_dimensions_with_little_data() {
+        // The header promises a gigapixel image, the data can't back that up
+        let data = zlib::compress(&[0; 1000], PngCompression::FixedHuffman);
+        let png = build_png(&[(b"IHDR", &rgb_header(40_000, 40_000)), (b"IDAT", &data), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::Malformed("not enough image data"))));
+    }
+
+    #[test]
+    fn unknown_critical_chunk() {
+        let data = zlib::compress(&[0, 0, 0, 0], PngCompression::Stored);
+        let png = build_png(&[(b"IHDR", &rgb_header(1, 1)), (b"IDAT", &data), (b"HUGE", &[]), (b"IEND", &[])]);
+        assert!(matches!(Canvas::from_png(&png), Err(PngError::Unsupported(what)) if what == "critical chunk HUGE"));
+    }
+}
@@ -0,0 +1,585 @@
+//! Just enough of zlib (RFC 1950) and deflate (RFC 1951) to store PNG image data
+
+use crate::canvas::png::PngCompression;
+
+/// How far back a match can reach
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+/// How many earlier positions with the same hash to try before settling on the best match so far
+const MAX_CHAIN: usize = 128;
+const HASH_BITS: u32 = 15;
+/// The most a stored block can hold
+const MAX_STORED: usize = u16::MAX as usize;
+
+/// Shortest length of each length symbol, 257 to 285
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258,
+];
+/// How many extra bits follow each length symbol
+const LENGTH_EXTRA: [u8; 29] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0];
+/// Shortest distance of each distance symbol
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145,
+    8193, 12289, 16385, 24577,
+];
+/// How many extra bits follow each distance symbol
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13,
+];
+/// The order a dynamic block lists the code lengths of its code length code in
+const CODE_LENGTH_ORDER: [usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// The checksum at the end of a zlib stream
+pub(super) fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+
+    let (mut a, mut b) = (1u32, 0u32);
+    // The most bytes that can be summed before `b` could overflow
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+/// Wrap `data` in a zlib stream, compressed as `compression` says
+pub(super) fn compress(data: &[u8], compression: PngCompression) -> Vec<u8> {
+    let mut w = BitWriter::default();
+    // Deflate with a 32K window and no preset dictionary. The check bits make it a multiple of 31.
+    w.bytes.extend_from_slice(&[0x78, 0x9c]);
+    match compression {
+        PngCompression::Stored => write_stored(&mut w, data),
+        PngCompression::FixedHuffman => write_fixed(&mut w, data),
+    }
+    w.align();
+
+    let mut bytes = w.bytes;
+    bytes.extend_from_slice(&adler32(data).to_be_bytes());
+    bytes
+}
+
+/// Unwrap a zlib stream.
+///
+/// Fails as soon as the data grows past `limit` bytes, so a few bytes of input can't ask for gigabytes of memory.
+pub(super) fn decompress(data: &[u8], limit: usize) -> Result<Vec<u8>, &'static str> {
+    let [cmf, flg, ..] = *data else {
+        return Err("missing zlib header");
+    };
+    if cmf & 0x0f != 8 || cmf >> 4 > 7 {
+        return Err("image data isn't deflate compressed");
+    }
+    if (u16::from(cmf) << 8 | u16::from(flg)) % 31 != 0 {
+        return Err("corrupt zlib header");
+    }
+    if flg & 0x20 != 0 {
+        return Err("image data uses a preset dictionary");
+    }
+
+    let mut r = BitReader { data, pos: 2, bit: 0 };
+    let mut out = Vec::new();
+    loop {
+        let last = r.bits(1)? == 1;
+        match r.bits(2)? {
+            0 => {
+                r.align();
+                let header = r.bytes(4)?;
+                let length = u16::from_le_bytes([header[0], header[1]]);
+                if length != !u16::from_le_bytes([header[2], header[3]]) {
+                    return Err("corrupt stored block length");
+                }
+                out.extend_from_slice(r.bytes(length as usize)?);
+                if out.len() > limit {
+                    return Err("more image data than the header says");
+                }
+            }
+            1 => {
+                let (literals, distances) = fixed_codes();
+                inflate_block(&mut r, &mut out, &literals, &distances, limit)?;
+            }
+            2 => {
+                let (literals, distances) = dynamic_codes(&mut r)?;
+                inflate_block(&mut r, &mut out, &literals, &distances, limit)?;
+            }
+            _ => return Err("invalid deflate block type"),
+        }
+        if last {
+            break;
+        }
+    }
+
+    r.align();
+    let checksum = r.bytes(4)?;
+    if u32::from_be_bytes([checksum[0], checksum[1], checksum[2], checksum[3]]) != adler32(&out) {
+        return Err("image data checksum mismatch");
+    }
+    Ok(out)
+}
+
+/// Store `data` as is, in as few blocks as it takes
+fn write_stored(w: &mut BitWriter, data: &[u8]) {
+    let count = data.len().div_ceil(MAX_STORED).max(1);
+    for i in 0..count {
+        let block = &data[i * MAX_STORED..((i + 1) * MAX_STORED).min(data.len())];
+        w.write((i + 1 == count) as u32, 1);
+        w.write(0, 2);
+        w.align();
+        let length = block.len() as u16;
+        w.bytes.extend_from_slice(&length.to_le_bytes());
+        w.bytes.extend_from_slice(&(!length).to_le_bytes());
+        w.bytes.extend_from_slice(block);
+    }
+}
+
+/// Compress `data` into a single block with the fixed Huffman codes
+fn write_fixed(w: &mut BitWriter, data: &[u8]) {
+    // Final block, fixed codes
+    w.write(1, 1);
+    w.write(1, 2);
+
+    let mut matcher = Matcher::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        match matcher.longest_match(data, pos) {
+            Some((length, distance)) => {
+                let i = LENGTH_BASE.partition_point(|&base| base as usize <= length) - 1;
+                write_fixed_literal(w, 257 + i as u16);
+                w.write((length - LENGTH_BASE[i] as usize) as u32, LENGTH_EXTRA[i] as u32);
+
+                let i = DISTANCE_BASE.partition_point(|&base| base as usize <= distance) - 1;
+                w.write_code(i as u32, 5);
+                w.write((distance - DISTANCE_BASE[i] as usize) as u32, DISTANCE_EXTRA[i] as u32);
+
+                for p in pos..pos + length {
+                    matcher.insert(data, p);
+                }
+                pos += length;
+            }
+            None => {
+                write_fixed_literal(w, data[pos] as u16);
+                matcher.insert(data, pos);
+                pos += 1;
+            }
+        }
+    }
+    // End of block
+    write_fixed_literal(w, 256);
+}
+
+/// Write a literal/length symbol with its fixed code
+fn write_fixed_literal(w: &mut BitWriter, symbol: u16) {
+    let (code, length) = match symbol {
+        0..=143 => (0x30 + symbol, 8),
+        144..=255 => (0x190 + symbol - 144, 9),
+        256..=279 => (symbol - 256, 7),
+        _ => (0xc0 + symbol - 280, 8),
+    };
+    w.write_code(code as u32, length);
+}
+
+/// Decode symbols into `out` until the end of the block
+fn inflate_block(
+    r: &mut BitReader,
+    out: &mut Vec<u8>,
+    literals: &Huffman,
+    distances: &Huffman,
+    limit: usize,
+) -> Result<(), &'static str> {
+    loop {
+        let symbol = literals.decode(r)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let i = symbol - 257;
+                if i >= LENGTH_BASE.len() {
+                    return Err("invalid length symbol");
+                }
+                let length = LENGTH_BASE[i] as usize + r.bits(LENGTH_EXTRA[i] as u32)? as usize;
+
+                let i = distances.decode(r)? as usize;
+                if i >= DISTANCE_BASE.len() {
+                    return Err("invalid distance symbol");
+                }
+                let distance = DISTANCE_BASE[i] as usize + r.bits(DISTANCE_EXTRA[i] as u32)? as usize;
+                if distance > out.len() {
+                    return Err("match distance reaches before the start of the data");
+                }
+
+                // Matches may overlap the bytes they produce, so copy one at a time
+                let start = out.len() - distance;
+                for p in start..start + length {
+                    out.push(out[p]);
+                }
+            }
+        }
+        if out.len() > limit {
+            return Err("more image data than the header says");
+        }
+    }
+}
+
+/// The literal/length and distance codes of blocks that use the fixed codes
+fn fixed_codes() -> (Huffman, Huffman) {
+    let mut lengths = [0u8; 288];
+    lengths[..144].fill(8);
+    lengths[144..256].fill(9);
+    lengths[256..280].fill(7);
+    lengths[280..].fill(8);
+    let literals = Huffman::new(&lengths).expect("the fixed codes are valid");
+    let distances = Huffman::new(&[5; 30]).expect("the fixed codes are valid");
+    (literals, distances)
+}
+
+/// Read the literal/length and distance codes from the header of a dynamic block
+fn dynamic_codes(r: &mut BitReader) -> Result<(Huffman, Huffman), &'static str> {
+    let literal_count = r.bits(5)? as usize + 257;
+    let distance_count = r.bits(5)? as usize + 1;
+    let code_length_count = r.bits(4)? as usize + 4;
+    if literal_count > 286 || distance_count > 30 {
+        return Err("too many length or distance codes");
+    }
+
+    let mut lengths = [0u8; 19];
+    for &i in &CODE_LENGTH_ORDER[..code_length_count] {
+        lengths[i] = r.bits(3)? as u8;
+    }
+    let code_lengths = Huffman::new(&lengths)?;
+
+    // Both codes' lengths are run length encoded as one sequence
+    let total = literal_count + distance_count;
+    let mut lengths = vec![0u8; total];
+    let mut i = 0;
+    while i < total {
+        let (length, repeat) = match code_lengths.decode(r)? {
+            symbol @ 0..=15 => (symbol as u8, 1),
+            16 if i == 0 => return Err("repeated code length with nothing before it"),
+            16 => (lengths[i - 1], 3 + r.bits(2)? as usize),
+            17 => (0, 3 + r.bits(3)? as usize),
+            _ => (0, 11 + r.bits(7)? as usize),
+        };
+        if i + repeat > total {
+            return Err("too many code lengths");
+        }
+        lengths[i..i + repeat].fill(length);
+        i += repeat;
+    }
+    if lengths[256] == 0 {
+        return Err("missing end of block code");
+    }
+
+    let literals = Huffman::new(&lengths[..literal_count])?;
+    let distances = Huffman::new(&lengths[literal_count..])?;
+    Ok((literals, distances))
+}
+
+/// A canonical Huffman code, decoded a bit at a time
+struct Huffman {
+    /// How many codes there are of each length
+    counts: [u16; 16],
+    /// The symbols, ordered by code
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// Build the code from the code length of each symbol, 0 for unused ones
+    fn new(lengths: &[u8]) -> Result<Self, &'static str> {
+        let mut counts = [0u16; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+
+        // Incomplete codes are fine, but there can't be more codes of a length than there is room for
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = 2 * left - count as i32;
+            if left < 0 {
+                return Err("over-subscribed Huffman code");
+            }
+        }
+
+        let mut offsets = [0u16; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Ok(Self { counts, symbols })
+    }
+
+    fn decode(&self, r: &mut BitReader) -> Result<u16, &'static str> {
+        // `code` is the bits read so far, `first` the first code of that length and `index` its symbol
+        let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &self.counts[1..] {
+            code |= r.bits(1)? as i32;
+            let count = count as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err("invalid Huffman code")
+    }
+}
+
+/// Finds earlier occurrences of the bytes at a position through chains of positions with the same hash
+struct Matcher {
+    /// The latest position with each hash, plus one so zero means none
+    head: Vec<u32>,
+    /// The position before each one with the same hash, again plus one
+    prev: Vec<u32>,
+}
+
+impl Matcher {
+    fn new() -> Self {
+        Self {
+            head: vec![0; 1 << HASH_BITS],
+            prev: vec![0; WINDOW_SIZE],
+        }
+    }
+
+    fn hash(data: &[u8], pos: usize) -> usize {
+        let bytes = u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], 0]);
+        (bytes.wrapping_mul(0x9e37_79b1) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, data: &[u8], pos: usize) {
+        if pos + MIN_MATCH > data.len() {
+            return;
+        }
+        let hash = Self::hash(data, pos);
+        self.prev[pos % WINDOW_SIZE] = self.head[hash];
+        self.head[hash] = pos as u32 + 1;
+    }
+
+    /// The length and distance of the longest earlier match for the bytes at `pos`, if there is one
+    fn longest_match(&self, data: &[u8], pos: usize) -> Option<(usize, usize)> {
+        if pos + MIN_MATCH > data.len() {
+            return None;
+        }
+        let ahead = &data[pos..(pos + MAX_MATCH).min(data.len())];
+
+        let mut best = (0, 0);
+        let mut candidate = self.head[Self::hash(data, pos)];
+        for _ in 0..MAX_CHAIN {
+            let Some(start) = (candidate as usize).checked_sub(1) else {
+                break;
+            };
+            let distance = pos - start;
+            if distance > WINDOW_SIZE {
+                break;
+            }
+            let length = data[start..].iter().zip(ahead).take_while(|(a, b)| a == b).count();
+            if length > best.0 {
+                best = (length, distance);
+                if length == ahead.len() {
+                    break;
+                }
+            }
+            candidate = self.prev[start % WINDOW_SIZE];
+        }
+        (best.0 >= MIN_MATCH).then_some(best)
+    }
+}
+
+/// Packs bits into bytes, least significant bit first
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: u64,
+    count: u32,
+}
+
+impl BitWriter {
+    /// Append the low `count` bits of `value`
+    fn write(&mut self, value: u32, count: u32) {
+        self.bits |= (value as u64) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.bytes.push(self.bits as u8);
+            self.bits >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Append a Huffman code, which goes most significant bit first
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Pad with zeros up to the next byte
+    fn align(&mut self) {
+        if self.count > 0 {
+            self.write(0, 8 - self.count);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    /// The next bit of `data[pos]`
+    bit: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// The next `count` bits, the first one read ending up least significant
+    fn bits(&mut self, count: u32) -> Result<u32, &'static str> {
+        let mut value = 0;
+        for i in 0..count {
+            let byte = self.data.get(self.pos).ok_or("unexpected end of image data")?;
+            value |= ((byte >> self.bit) as u32 & 1) << i;
+            self.bit += 1;
+            if self.bit == 8 {
+                self.bit = 0;
+                self.pos += 1;
+            }
+        }
+        Ok(value)
+    }
+
+    /// Skip to the start of the next byte
+    fn align(&mut self) {
+        if self.bit > 0 {
+            self.bit = 0;
+            self.pos += 1;
+        }
+    }
+
+    /// The next `count` whole bytes, the reader must be aligned
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], &'static str> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + count)
+            .ok_or("unexpected end of image data")?;
+        self.pos += count;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Something with both long runs and short repeats
+    fn sample_data() -> Vec<u8> {
+        let mut data = b"Gabes Graphics renders spheres, planes, cubes and cones. ".repeat(40);
+        data.extend_from_slice(&[0; 1000]);
+        data.extend((0..20_000u32).map(|i| (i * i / 7 % 251) as u8));
+        data
+    }
+
+    #[test]
+    fn adler32() {
+        assert_eq!(super::adler32(b""), 1);
+        assert_eq!(super::adler32(b"Wikipedia"), 0x11e6_0398);
+        // Long enough to wrap the sums a few times
+        assert_eq!(super::adler32(&[0xff; 100_000]), 0x149a_302c);
+    }
+
+    #[test]
+    fn stored_round_trip() {
+        for data in [Vec::new(), b"abc".to_vec(), vec![7; 150_000], sample_data()] {
+            let compressed = compress(&data, PngCompression::Stored);
+            // Five bytes of block header for every 64K, plus the zlib header and checksum
+            assert_eq!(compressed.len(), 6 + data.len() + 5 * data.len().div_ceil(MAX_STORED).max(1));
+            assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn fixed_round_trip() {
+        for data in [Vec::new(), b"abc".to_vec(), vec![7; 150_000], sample_data()] {
+            let compressed = compress(&data, PngCompression::FixedHuffman);
+            assert_eq!(decompress(&compressed, data.len()).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn fixed_compresses() {
+        let data = sample_data();
+        assert!(compress(&data, PngCompression::FixedHuffman).len() < data.len() / 2);
+        assert!(compress(&[0; 100_000], PngCompression::FixedHuffman).len() < 1000);
+    }
+
+    #[test]
+    fn decompress_dynamic_block() {
+        // zlib's own output at level 9, which picks a dynamic block
+        let compressed = [
+            0x78, 0xda, 0x1d, 0xc7, 0xdb, 0x09, 0xc0, 0x30, 0x0c, 0x03, 0xc0, 0x55, 0x34, 0x40, 0xe9, 0x1a, 0x99, 0xc3,
+            0x49, 0x44, 0x13, 0x08, 0xae, 0xb1, 0xda, 0xfd, 0xfb, 0xf8, 0x3a, 0xae, 0x58, 0xa5, 0x50, 0xd2, 0x62, 0xcc,
+            0x26, 0x24, 0xbd, 0x33, 0x05, 0xc5, 0x60, 0x52, 0x1b, 0x62, 0x99, 0x7f, 0xb6, 0xbb, 0xfe, 0x9c, 0xef, 0x60,
+            0xde, 0x71, 0xe5, 0x34, 0x3f, 0x16, 0xb5, 0x3f, 0x3e, 0x81, 0x18, 0x5c,
+        ];
+        let expected = b"Gabes Graphics renders spheres, planes, cubes, cones and triangles.";
+        assert_eq!(decompress(&compressed, 100).unwrap(), expected);
+    }
+
+    #[test]
+    fn decompress_limit() {
+        let compressed = compress(&[1; 1000], PngCompression::FixedHuffman);
+        assert!(decompress(&compressed, 1000).is_ok());
+        assert!(decompress(&compressed, 999).is_err());
+    }
+
+    #[test]
+    fn corrupt_data() {
+        let data = sample_data();
+        let compressed = compress(&data, PngCompression::FixedHuffman);
+
+        // Truncated
+        assert!(decompress(&compressed[..compressed.len() / 2], data.len()).is_err());
+        // Checksum
+        let mut bad = compressed.clone();
+        *bad.last_mut().unwrap() ^= 1;
+        assert_eq!(decompress(&bad, data.len()), Err("image data checksum mismatch"));
+        // Header
+        assert!(decompress(&[0x78, 0x9d, 0x03, 0x00], 10).is_err());
+        assert!(decompress(&[0x79, 0x9c, 0x03, 0x00], 10).is_err());
+        // Block type 3
+        assert_eq!(decompress(&[0x78, 0x9c, 0x07, 0x00], 10), Err("invalid deflate block type"));
+        // Stored length that doesn't match its complement
+        assert_eq!(
+            decompress(&[0x78, 0x9c, 0x01, 0x01, 0x00, 0x00, 0x00, 0x61], 10),
+            Err("corrupt stored block length")
+        );
+    }
+
+    #[test]
+    fn distance_before_start() {
+        let mut w = BitWriter::default();
+        w.bytes.extend_from_slice(&[0x78, 0x9c]);
+        w.write(1, 1);
+        w.write(1, 2);
+        write_fixed_literal(&mut w, b'a' as u16);
+        // Length 3, distance 2, one byte more than there is
+        write_fixed_literal(&mut w, 257);
+        w.write_code(1, 5);
+        write_fixed_literal(&mut w, 256);
+        w.align();
+        assert_eq!(
+            decompress(&w.bytes, 10),
+            Err("match distance reaches before the start of the data")
+        );
+    }
+
+    #[test]
+    fn over_subscribed_code() {
+        assert!(Huffman::new(&[1, 1, 1]).is_err());
+        assert!(Huffman::new(&[1, 2, 2]).is_ok());
+        // A single code is incomplete, but allowed
+        assert!(Huffman::new(&[0, 1]).is_ok());
+    }
+}
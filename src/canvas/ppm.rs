@@ -0,0 +1,604 @@
+use std::fmt;
+use std::io::{self, BufWriter, Read, Write};
+use crate::canvas::color::Color;
+use crate::canvas::{write_to_vec, Canvas};
+
+/// Netpbm asks for plain PPM lines to be no longer than this
+const MAX_LINE_LENGTH: usize = 70;
+
+/// How `Canvas::to_ppm_with` lays out the samples of a plain PPM file
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PpmLayout {
+    /// One pixel per line, what `to_ppm` writes
+    #[default]
+    PixelPerLine,
+    /// Every row packed into as few lines as possible, each at most 70 characters long.
+    /// Numbers are never split, and every row starts on a new line.
+    Wrapped,
+}
+
+/// Settings for writing plain PPM files
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PpmOptions {
+    pub layout: PpmLayout,
+    /// The value full brightness is written as, from 1 up to 65535. 255 by default.
+    pub maxval: u16,
+}
+
+impl PpmOptions {
+    /// Wrapped lines, the layout other netpbm tools write
+    pub fn wrapped() -> Self {
+        Self {
+            layout: PpmLayout::Wrapped,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for PpmOptions {
+    fn default() -> Self {
+        Self {
+            layout: PpmLayout::PixelPerLine,
+            maxval: 255,
+        }
+    }
+}
+
+impl Canvas {
+    /// Write the contents of the canvas to a PPM file, laid out as `options` says
+    ///
+    /// # Panics
+    ///
+    /// If `options.maxval` is 0.
+    pub fn to_ppm_with(&self, options: &PpmOptions) -> String {
+        let data = write_to_vec(|data| self.write_ppm_with(data, options));
+        String::from_utf8(data).expect("plain PPM files are ASCII")
+    }
+
+    /// Stream the canvas to `writer` as a plain PPM file, one pixel per line like `to_ppm`
+    pub fn write_ppm<W: Write>(&self, writer: W) -> io::Result<()> {
+        self.write_ppm_with(writer, &PpmOptions::default())
+    }
+
+    /// Stream the canvas to `writer` as a plain PPM file, laid out as `options` says.
+    ///
+    /// A `maxval` of 0 is an `InvalidInput` error.
+    pub fn write_ppm_with<W: Write>(&self, writer: W, options: &PpmOptions) -> io::Result<()> {
+        let max_color_value = options.maxval;
+        check_maxval(max_color_value)?;
+
+        let mut w = BufWriter::new(writer);
+        write!(w, "P3\n{} {}\n{}\n", self.width, self.height, max_color_value)?;
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let row = row.iter().map(|pixel| pixel.scale(max_color_value));
+            match options.layout {
+                PpmLayout::PixelPerLine => {
+                    for (r, g, b) in row {
+                        writeln!(w, "{r} {g} {b}")?;
+                    }
+                }
+                PpmLayout::Wrapped => {
+                    let mut line_length = 0;
+                    for sample in row.flat_map(|(r, g, b)| [r, g, b]) {
+                        let length = digits(sample);
+                        if line_length == 0 {
+                            line_length = length;
+                        } else if line_length + 1 + length > MAX_LINE_LENGTH {
+                            w.write_all(b"\n")?;
+                            line_length = length;
+                        } else {
+                            w.write_all(b" ")?;
+                            line_length += 1 + length;
+                        }
+                        write!(w, "{sample}")?;
+                    }
+                    w.write_all(b"\n")?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    /// Write the contents of the canvas to a binary (P6) PPM file.
+    ///
+    /// Samples take one byte each when `maxval` is below 256, and two big-endian bytes otherwise.
+    /// This is far smaller than a plain file, and the only practical way to keep 16 bits.
+    ///
+    /// # Panics
+    ///
+    /// If `maxval` is 0.
+    pub fn to_p6(&self, maxval: u16) -> Vec<u8> {
+        write_to_vec(|data| self.write_p6(data, maxval))
+    }
+
+    /// Stream the canvas to `writer` as a binary (P6) PPM file, see `to_p6`.
+    ///
+    /// A `maxval` of 0 is an `InvalidInput` error.
+    pub fn write_p6<W: Write>(&self, writer: W, maxval: u16) -> io::Result<()> {
+        check_maxval(maxval)?;
+
+        let mut w = BufWriter::new(writer);
+        write!(w, "P6\n{} {}\n{}\n", self.width, self.height, maxval)?;
+        for pixel in &self.pixels {
+            let (r, g, b) = pixel.scale(maxval);
+            for sample in [r, g, b] {
+                if maxval < 256 {
+                    w.write_all(&[sample as u8])?;
+                } else {
+                    w.write_all(&sample.to_be_bytes())?;
+                }
+            }
+        }
+        w.flush()
+    }
+
+    /// Load a PPM image, plain (P3) or binary (P6).
+    ///
+    /// Binary files are rarely valid UTF-8, use `read_ppm` for those.
+    pub fn from_ppm(ppm: &str) -> Result<Canvas, PpmError> {
+        parse(ppm.as_bytes())
+    }
+
+    /// Load a PPM image, plain (P3) or binary (P6), from a file or anything else readable
+    pub fn read_ppm<R: Read>(mut reader: R) -> Result<Canvas, PpmError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        parse(&bytes)
+    }
+}
+
+/// Parse a whole PPM file.
+///
+/// The header is the magic number, width, height and the max color value, separated by
+/// whitespace and `#` comments. Samples are scaled from 0..=maxval down to 0.0..=1.0.
+fn parse(bytes: &[u8]) -> Result<Canvas, PpmError> {
+    let mut parser = Parser { bytes, pos: 0 };
+
+    let binary = match parser.token()? {
+        b"P3" => false,
+        b"P6" => true,
+        magic => return Err(PpmError::UnknownFormat(String::from_utf8_lossy(magic).into_owned())),
+    };
+    let width = parser.number()? as usize;
+    let height = parser.number()? as usize;
+    let maxval = parser.number()?;
+    if !(1..=65535).contains(&maxval) {
+        return Err(PpmError::InvalidMaxval(maxval));
+    }
+
+    if binary {
+        // Exactly one whitespace character between the header and the raster. Anything else, like a comment,
+        // would otherwise be read as pixels.
+        match bytes.get(parser.pos) {
+            Some(b) if b.is_ascii_whitespace() => parser.pos += 1,
+            Some(&b) => return Err(PpmError::MissingSeparator(b)),
+            None => return Err(PpmError::UnexpectedEof),
+        }
+    }
+
+    // Don't allocate a canvas the file can't possibly fill. Binary samples take one or two bytes,
+    // plain ones at least a digit and a separator.
+    let sample_size = match (binary, maxval) {
+        (true, 0..=255) => 1,
+        (true, _) => 2,
+        (false, _) => 2,
+    };
+    let needed = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(3 * sample_size))
+        .ok_or(PpmError::UnexpectedEof)?;
+    if bytes.len().saturating_sub(parser.pos) + 1 < needed {
+        return Err(PpmError::UnexpectedEof);
+    }
+
+    let mut canvas = Canvas::new(width, height);
+    for pixel in canvas.pixels.iter_mut() {
+        let mut rgb = [0.0; 3];
+        for sample in rgb.iter_mut() {
+            let value = if binary {
+                parser.binary_sample(maxval)?
+            } else {
+                parser.number()?
+            };
+            if value > maxval {
+                return Err(PpmError::SampleOutOfRange { value, maxval });
+            }
+            *sample = value as f32 / maxval as f32;
+        }
+        *pixel = Color::new(rgb[0], rgb[1], rgb[2]);
+    }
+    Ok(canvas)
+}
+
+/// A max color value of 0 leaves no room for any brightness at all
+fn check_maxval(maxval: u16) -> io::Result<()> {
+    if maxval == 0 {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "the max color value must be at least 1"));
+    }
+    Ok(())
+}
+
+/// How many characters `n` takes to write out
+fn digits(n: u16) -> usize {
+    n.checked_ilog10().map_or(1, |log| log as usize + 1)
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    /// The next run of non-whitespace, skipping whitespace and comments before it
+    fn token(&mut self) -> Result<&'a [u8], PpmError> {
+        loop {
+            match self.bytes.get(self.pos) {
+                Some(b) if b.is_ascii_whitespace() => self.pos += 1,
+                Some(b'#') => {
+                    while self.bytes.get(self.pos).is_some_and(|&b| b != b'\n' && b != b'\r') {
+                        self.pos += 1;
+                    }
+                }
+                Some(_) => break,
+                None => return Err(PpmError::UnexpectedEof),
+            }
+        }
+
+        let start = self.pos;
+        while self.bytes.get(self.pos).is_some_and(|b| !b.is_ascii_whitespace() && *b != b'#') {
+            self.pos += 1;
+        }
+        Ok(&self.bytes[start..self.pos])
+    }
+
+    fn number(&mut self) -> Result<u32, PpmError> {
+        let token = self.token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .ok_or_else(|| PpmError::InvalidNumber(String::from_utf8_lossy(token).into_owned()))
+    }
+
+    /// One raw sample, a byte when `maxval` fits in one and two big-endian bytes otherwise
+    fn binary_sample(&mut self, maxval: u32) -> Result<u32, PpmError> {
+        let size = if maxval < 256 { 1 } else { 2 };
+        let bytes = self.bytes.get(self.pos..self.pos + size).ok_or(PpmError::UnexpectedEof)?;
+        self.pos += size;
+        Ok(bytes.iter().fold(0, |acc, &b| acc << 8 | b as u32))
+    }
+}
+
+/// Why a PPM image could not be loaded
+#[derive(Debug)]
+pub enum PpmError {
+    Io(io::Error),
+    /// Not a P3 or P6 file
+    UnknownFormat(String),
+    /// The file ends before all of the pixels
+    UnexpectedEof,
+    /// Something other than a number where the header or a P3 sample should be
+    InvalidNumber(String),
+    /// A max color value outside of 1..=65535
+    InvalidMaxval(u32),
+    /// The byte after a binary header, where a single whitespace character has to be
+    MissingSeparator(u8),
+    /// A sample greater than the max color value
+    SampleOutOfRange { value: u32, maxval: u32 },
+}
+
+impl fmt::Display for PpmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::UnknownFormat(magic) => write!(f, "unknown format `{magic}`, expected P3 or P6"),
+            Self::UnexpectedEof => write!(f, "unexpected end of file"),
+            Self::InvalidNumber(value) => write!(f, "invalid number `{value}`"),
+            Self::InvalidMaxval(maxval) => write!(f, "max color value {maxval} is not within 1..=65535"),
+            Self::MissingSeparator(b) => {
+                write!(f, "expected whitespace between the header and the pixels, found `{}`", b.escape_ascii())
+            }
+            Self::SampleOutOfRange { value, maxval } => {
+                write!(f, "sample {value} is greater than the max color value {maxval}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PpmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for PpmError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::canvas::FullWriter;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn wrapped_lines() {
+        let mut canvas = Canvas::new(10, 2);
+        for y in 0..2 {
+            for x in 0..10 {
+                canvas.set_pixel(x, y, Color::new(1.0, 0.8, 0.6));
+            }
+        }
+        let ppm = canvas.to_ppm_with(&PpmOptions::wrapped());
+        let row = "255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204\n\
+                   153 255 204 153 255 204 153 255 204 153 255 204 153\n";
+        assert_eq!(ppm, format!("P3\n10 2\n255\n{row}{row}"));
+    }
+
+    #[test]
+    fn wrapped_lines_are_short() {
+        let mut canvas = Canvas::new(37, 3);
+        for y in 0..3 {
+            for x in 0..37 {
+                canvas.set_pixel(x, y, Color::new(x as f32 / 37.0, 1.0 - y as f32 / 3.0, 0.04));
+            }
+        }
+        let ppm = canvas.to_ppm_with(&PpmOptions::wrapped());
+        assert!(ppm.ends_with('\n'));
+        assert!(ppm.lines().all(|line| line.len() <= MAX_LINE_LENGTH && !line.ends_with(' ')));
+        // Nothing is lost or split along the way
+        let loaded = Canvas::from_ppm(&ppm).unwrap();
+        assert_relative_eq!(loaded.get_pixel(36, 2), canvas.get_pixel(36, 2), epsilon = 1.0 / 255.0);
+        assert_relative_eq!(loaded.get_pixel(12, 1), canvas.get_pixel(12, 1), epsilon = 1.0 / 255.0);
+    }
+
+    #[test]
+    fn default_is_pixel_per_line() {
+        let canvas = Canvas::new(2, 1);
+        assert_eq!(canvas.to_ppm_with(&PpmOptions::default()), canvas.to_ppm());
+        assert_eq!(canvas.to_ppm(), "P3\n2 1\n255\n0 0 0\n0 0 0\n");
+    }
+
+    #[test]
+    fn plain_16_bit() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.5, 0.0));
+        let options = PpmOptions {
+            maxval: 65535,
+            ..PpmOptions::default()
+        };
+        assert_eq!(canvas.to_ppm_with(&options), "P3\n2 1\n65535\n65535 32768 0\n0 0 0\n");
+    }
+
+    #[test]
+    fn binary_output() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.2, 0.0));
+        canvas.set_pixel(1, 0, Color::new(0.0, 0.4, 1.5));
+        let mut expected = b"P6\n2 1\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 51, 0, 0, 102, 255]);
+        assert_eq!(canvas.to_p6(255), expected);
+    }
+
+    #[test]
+    fn binary_16_bit_output() {
+        let mut canvas = Canvas::new(1, 1);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.5, 0.0));
+        let mut expected = b"P6\n1 1\n65535\n".to_vec();
+        expected.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        assert_eq!(canvas.to_p6(65535), expected);
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let mut canvas = Canvas::new(4, 3);
+        for y in 0..3 {
+            for x in 0..4 {
+                canvas.set_pixel(x, y, Color::new(x as f32 / 3.0, y as f32 / 2.0, 0.123_456));
+            }
+        }
+        for (maxval, epsilon) in [(255, 1.0 / 255.0), (1000, 1.0 / 1000.0), (65535, 1.0 / 65535.0)] {
+            let loaded = Canvas::read_ppm(canvas.to_p6(maxval).as_slice()).unwrap();
+            for y in 0..3 {
+                for x in 0..4 {
+                    assert_relative_eq!(loaded.get_pixel(x, y), canvas.get_pixel(x, y), epsilon = epsilon);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn write_matches_to_ppm() {
+        let mut canvas = Canvas::new(30, 4);
+        canvas.set_pixel(3, 2, Color::new(0.3, 0.7, 1.0));
+        for options in [PpmOptions::default(), PpmOptions::wrapped()] {
+            let mut written = Vec::new();
+            canvas.write_ppm_with(&mut written, &options).unwrap();
+            assert_eq!(String::from_utf8(written).unwrap(), canvas.to_ppm_with(&options));
+        }
+
+        let mut written = Vec::new();
+        canvas.write_ppm(&mut written).unwrap();
+        assert_eq!(String::from_utf8(written).unwrap(), canvas.to_ppm());
+
+        let mut written = Vec::new();
+        canvas.write_p6(&mut written, 4095).unwrap();
+        assert_eq!(written, canvas.to_p6(4095));
+    }
+
+    #[test]
+    fn write_errors_are_passed_on() {
+        let canvas = Canvas::new(2, 2);
+        assert_eq!(canvas.write_ppm(FullWriter).unwrap_err().kind(), io::ErrorKind::StorageFull);
+        assert_eq!(canvas.write_p6(FullWriter, 255).unwrap_err().kind(), io::ErrorKind::StorageFull);
+    }
+
+    #[test]
+    fn zero_maxval_is_an_error() {
+        let canvas = Canvas::new(2, 2);
+        let options = PpmOptions {
+            maxval: 0,
+            ..PpmOptions::default()
+        };
+        let err = canvas.write_ppm_with(Vec::new(), &options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(canvas.write_p6(Vec::new(), 0).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn digits() {
+        assert_eq!(super::digits(0), 1);
+        assert_eq!(super::digits(9), 1);
+        assert_eq!(super::digits(10), 2);
+        assert_eq!(super::digits(255), 3);
+        assert_eq!(super::digits(65535), 5);
+    }
+
+    #[test]
+    fn wrong_magic_number() {
+        let ppm = "P32\n1 1\n255\n0 0 0\n";
+        assert!(matches!(Canvas::from_ppm(ppm), Err(PpmError::UnknownFormat(magic)) if magic == "P32"));
+    }
+
+    #[test]
+    fn dimensions() {
+        let canvas = Canvas::from_ppm("P3\n10 2\n255\n").unwrap_err();
+        assert!(matches!(canvas, PpmError::UnexpectedEof));
+
+        let ppm = format!("P3\n10 2\n255\n{}", "0 0 0\n".repeat(20));
+        let canvas = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(canvas.width(), 10);
+        assert_eq!(canvas.height(), 2);
+    }
+
+    #[test]
+    fn pixel_data() {
+        let ppm = "P3\n4 3\n255\n\
+                   255 127 0  0 127 255  127 255 0  255 255 255\n\
+                   0 0 0  255 0 0  0 255 0  0 0 255\n\
+                   255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        let cases = [
+            (0, 0, Color::new(1.0, 0.49804, 0.0)),
+            (1, 0, Color::new(0.0, 0.49804, 1.0)),
+            (2, 0, Color::new(0.49804, 1.0, 0.0)),
+            (3, 0, Color::new(1.0, 1.0, 1.0)),
+            (0, 1, Color::new(0.0, 0.0, 0.0)),
+            (1, 1, Color::new(1.0, 0.0, 0.0)),
+            (2, 1, Color::new(0.0, 1.0, 0.0)),
+            (3, 1, Color::new(0.0, 0.0, 1.0)),
+            (0, 2, Color::new(1.0, 1.0, 0.0)),
+            (1, 2, Color::new(0.0, 1.0, 1.0)),
+            (2, 2, Color::new(1.0, 0.0, 1.0)),
+            (3, 2, Color::new(0.49804, 0.49804, 0.49804)),
+        ];
+        for (x, y, expected) in cases {
+            assert_relative_eq!(canvas.get_pixel(x, y), expected, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn comments() {
+        let ppm = "P3\n# this is a comment\n2 1\n# this, too\n255\n# another comment\n255 255 255\n# oh, no, comments in the pixel data!\n255 0 255\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 1.0, 1.0));
+        assert_relative_eq!(canvas.get_pixel(1, 0), Color::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn samples_split_across_lines() {
+        let ppm = "P3  1 1\t255#comment right after\n51\n\n153\r\n\n204\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(0.2, 0.6, 0.8));
+    }
+
+    #[test]
+    fn other_maxval() {
+        let ppm = "P3\n2 2\n100\n100 100 100  50 50 50\n75 50 25  0 0 0\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 1), Color::new(0.75, 0.5, 0.25));
+
+        let ppm = "P3\n1 1\n65535\n65535 32768 0\n";
+        let canvas = Canvas::from_ppm(ppm).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.5, 0.0), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn binary() {
+        let mut ppm = b"P6\n# binary\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 51, 0, 102, 255]);
+        let canvas = Canvas::read_ppm(ppm.as_slice()).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.0, 0.2));
+        assert_relative_eq!(canvas.get_pixel(1, 0), Color::new(0.0, 0.4, 1.0));
+    }
+
+    #[test]
+    fn binary_header_needs_whitespace() {
+        let mut ppm = b"P6 1 1 255#c\n".to_vec();
+        ppm.extend_from_slice(&[10, 32, 35]);
+        let err = Canvas::read_ppm(ppm.as_slice()).unwrap_err();
+        assert!(matches!(err, PpmError::MissingSeparator(b'#')));
+        assert!(matches!(Canvas::read_ppm(b"P6 1 1 255".as_slice()), Err(PpmError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn binary_whitespace_byte_is_data() {
+        // The single whitespace after maxval ends the header, a 10 ('\n') after it is a sample
+        let mut ppm = b"P6 1 1 255\n".to_vec();
+        ppm.extend_from_slice(&[10, 32, 35]);
+        let canvas = Canvas::read_ppm(ppm.as_slice()).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(10.0 / 255.0, 32.0 / 255.0, 35.0 / 255.0));
+    }
+
+    #[test]
+    fn binary_16_bit() {
+        let mut ppm = b"P6\n1 1\n65535\n".to_vec();
+        ppm.extend_from_slice(&[0xff, 0xff, 0x80, 0x00, 0x00, 0x00]);
+        let canvas = Canvas::read_ppm(ppm.as_slice()).unwrap();
+        assert_relative_eq!(canvas.get_pixel(0, 0), Color::new(1.0, 0.5, 0.0), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn binary_truncated() {
+        let mut ppm = b"P6\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 51, 0]);
+        assert!(matches!(Canvas::read_ppm(ppm.as_slice()), Err(PpmError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut canvas = Canvas::new(3, 2);
+        canvas.set_pixel(0, 0, Color::new(1.0, 0.0, 0.2));
+        canvas.set_pixel(2, 1, Color::new(0.4, 0.6, 0.8));
+        let loaded = Canvas::from_ppm(&canvas.to_ppm()).unwrap();
+        assert_eq!(loaded.width(), 3);
+        assert_eq!(loaded.height(), 2);
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_relative_eq!(loaded.get_pixel(x, y), canvas.get_pixel(x, y), epsilon = 1.0 / 255.0);
+            }
+        }
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(Canvas::from_ppm("P3\n1 x\n255\n"), Err(PpmError::InvalidNumber(n)) if n == "x"));
+        assert!(matches!(Canvas::from_ppm("P3\n1 1\n0\n"), Err(PpmError::InvalidMaxval(0))));
+        assert!(matches!(Canvas::from_ppm("P3\n1 1\n70000\n"), Err(PpmError::InvalidMaxval(70000))));
+        assert!(matches!(
+            Canvas::from_ppm("P3\n1 1\n100\n0 101 0\n"),
+            Err(PpmError::SampleOutOfRange { value: 101, maxval: 100 })
+        ));
+        assert!(matches!(Canvas::from_ppm(""), Err(PpmError::UnexpectedEof)));
+    }
+
+    #[test]
+    fn error_message() {
+        let err = Canvas::from_ppm("P3\n1 1\n100\n0 101 0\n").unwrap_err();
+        assert_eq!(err.to_string(), "sample 101 is greater than the max color value 100");
+    }
+}
@@ -0,0 +1,400 @@
+use std::ops::Deref;
+use nalgebra::{Point3, Vector3};
+use crate::math::{reflect, EPSILON};
+use crate::math::ray::Ray;
+use crate::shape::Shape;
+
+/// A single ray-object intersection: the distance `t` along the ray and the object that was hit.
+#[derive(Debug, Copy, Clone)]
+pub struct Intersection<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    /// Where on the surface the hit is, for shapes that have surface coordinates like triangles.
+    /// Zero for everything else.
+    pub u: f32,
+    pub v: f32,
+}
+
+impl<'a> Intersection<'a> {
+    pub fn new(t: f32, object: &'a dyn Shape) -> Self {
+        Self::with_uv(t, object, 0.0, 0.0)
+    }
+
+    /// An intersection that also remembers the surface coordinates of the hit
+    pub fn with_uv(t: f32, object: &'a dyn Shape, u: f32, v: f32) -> Self {
+        Self { t, object, u, v }
+    }
+
+    /// Work out everything shading needs to know about this intersection of `ray`.
+    ///
+    /// `xs` are all the intersections along the ray, sorted, this one included.
+    /// They tell which objects the ray is inside of on either side of the hit.
+    pub fn prepare_computations(&self, ray: &Ray, xs: &[Intersection<'a>]) -> Computations<'a> {
+        let point = ray.position(self.t);
+        let eyev = -ray.direction.normalize();
+        let mut normalv = self.object.normal_at_hit(&point, self);
+
+        // The normal always faces the eye, even if we are looking at the surface from the inside
+        let inside = normalv.dot(&eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+
+        let (n1, n2) = self.refractive_indices(xs);
+
+        Computations {
+            t: self.t,
+            object: self.object,
+            point,
+            eyev,
+            normalv,
+            inside,
+            reflectv: reflect(&ray.direction, &normalv),
+            over_point: point + normalv * EPSILON,
+            under_point: point - normalv * EPSILON,
+            n1,
+            n2,
+        }
+    }
+
+    /// The refractive indices of the media the ray leaves (n1) and enters (n2) at this intersection.
+    ///
+    /// Walks the intersections in order, keeping track of which objects contain the ray so far:
+    /// entering an object adds it, exiting removes it. The innermost one is the current medium,
+    /// and empty space (no container) is a vacuum with an index of 1.0.
+    fn refractive_indices(&self, xs: &[Intersection<'a>]) -> (f32, f32) {
+        let index_of = |containers: &[&dyn Shape]| {
+            containers.last().map_or(1.0, |object| object.material().refractive_index)
+        };
+
+        let mut containers: Vec<&dyn Shape> = Vec::new();
+        let mut n1 = 1.0;
+        for i in xs {
+            if i == self {
+                n1 = index_of(&containers);
+            }
+
+            match containers.iter().position(|object| std::ptr::addr_eq(*object, i.object)) {
+                Some(position) => {
+                    containers.remove(position);
+                }
+                None => containers.push(i.object),
+            }
+
+            if i == self {
+                return (n1, index_of(&containers));
+            }
+        }
+        (n1, index_of(&containers))
+    }
+}
+
+/// Two intersections are equal when they hit the *same* object at the same `t`.
+impl PartialEq for Intersection<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.t == other.t && std::ptr::addr_eq(self.object, other.object)
+    }
+}
+
+/// Precomputed data about a hit, everything needed to shade it.
+#[derive(Debug, Copy, Clone)]
+pub struct Computations<'a> {
+    pub t: f32,
+    pub object: &'a dyn Shape,
+    /// The world space point of intersection
+    pub point: Point3<f32>,
+    /// Points from `point` toward the eye
+    pub eyev: Vector3<f32>,
+    /// The surface normal, flipped when needed so that it faces the eye
+    pub normalv: Vector3<f32>,
+    /// Did the ray hit the surface from the inside of the object?
+    pub inside: bool,
+    /// The ray's direction bounced off the surface
+    pub reflectv: Vector3<f32>,
+    /// `point` nudged just above the surface, the origin of rays leaving it
+    pub over_point: Point3<f32>,
+    /// `point` nudged just below the surface, the origin of rays entering it
+    pub under_point: Point3<f32>,
+    /// The refractive index of the medium the ray is leaving
+    pub n1: f32,
+    /// The refractive index of the medium the ray is entering
+    pub n2: f32,
+}
+
+impl Computations<'_> {
+    /// The Schlick approximation of the Fresnel effect: the fraction of light that is
+    /// reflected rather than refracted at this hit, from 0.0 to 1.0.
+    pub fn schlick(&self) -> f32 {
+        let mut cos = self.eyev.dot(&self.normalv);
+
+        // Total internal reflection can only happen going into a less dense medium
+        if self.n1 > self.n2 {
+            let n = self.n1 / self.n2;
+            let sin2_t = n * n * (1.0 - cos * cos);
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            // Use the angle of the refracted ray instead
+            cos = (1.0 - sin2_t).sqrt();
+        }
+
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
+
+/// A collection of intersections, always kept sorted by `t`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Intersections<'a> {
+    list: Vec<Intersection<'a>>,
+}
+
+impl<'a> Intersections<'a> {
+    pub fn new(mut list: Vec<Intersection<'a>>) -> Self {
+        list.sort_by(|a, b| a.t.total_cmp(&b.t));
+        Self { list }
+    }
+
+    /// The visible intersection: the one with the lowest non-negative `t`.
+    ///
+    /// Intersections behind the ray origin (negative `t`) are never visible.
+    pub fn hit(&self) -> Option<&Intersection<'a>> {
+        self.list.iter().find(|i| i.t >= 0.0)
+    }
+}
+
+/// `Intersections` reads like a slice: `xs.len()`, `xs[0]`, `xs.iter()`
+impl<'a> Deref for Intersections<'a> {
+    type Target = [Intersection<'a>];
+
+    fn deref(&self) -> &Self::Target {
+        &self.list
+    }
+}
+
+impl<'a> IntoIterator for Intersections<'a> {
+    type Item = Intersection<'a>;
+    type IntoIter = std::vec::IntoIter<Intersection<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.list.into_iter()
+    }
+}
+
+/// Collecting keeps the sort invariant, so intersections from several objects can be merged.
+impl<'a> FromIterator<Intersection<'a>> for Intersections<'a> {
+    fn from_iter<I: IntoIterator<Item = Intersection<'a>>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use crate::shape::sphere::Sphere;
+
+    #[test]
+    fn new() {
+        let s = Sphere::new();
+        let i = Intersection::new(3.5, &s);
+        assert_relative_eq!(i.t, 3.5);
+        assert!(std::ptr::addr_eq(i.object, &s));
+    }
+
+    #[test]
+    fn with_uv() {
+        let s = Sphere::new();
+        let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+        assert_relative_eq!(i.u, 0.2);
+        assert_relative_eq!(i.v, 0.4);
+    }
+
+    #[test]
+    fn aggregate() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
+        let xs = Intersections::new(vec![i2, i1]);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 1.0);
+        assert_relative_eq!(xs[1].t, 2.0);
+    }
+
+    #[test]
+    fn hit_all_positive() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(1.0, &s);
+        let i2 = Intersection::new(2.0, &s);
+        let xs = Intersections::new(vec![i2, i1]);
+        assert_eq!(xs.hit(), Some(&i1));
+    }
+
+    #[test]
+    fn hit_some_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-1.0, &s);
+        let i2 = Intersection::new(1.0, &s);
+        let xs = Intersections::new(vec![i2, i1]);
+        assert_eq!(xs.hit(), Some(&i2));
+    }
+
+    #[test]
+    fn hit_all_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(-2.0, &s);
+        let i2 = Intersection::new(-1.0, &s);
+        let xs = Intersections::new(vec![i2, i1]);
+        assert_eq!(xs.hit(), None);
+    }
+
+    #[test]
+    fn hit_is_lowest_non_negative() {
+        let s = Sphere::new();
+        let i1 = Intersection::new(5.0, &s);
+        let i2 = Intersection::new(7.0, &s);
+        let i3 = Intersection::new(-3.0, &s);
+        let i4 = Intersection::new(2.0, &s);
+        let xs = Intersections::new(vec![i1, i2, i3, i4]);
+        assert_eq!(xs.hit(), Some(&i4));
+    }
+
+    #[test]
+    fn computations() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(comps.t, i.t);
+        assert!(std::ptr::addr_eq(comps.object, &shape));
+        assert_relative_eq!(comps.point, Point3::new(0.0, 0.0, -1.0));
+        assert_relative_eq!(comps.eyev, Vector3::new(0.0, 0.0, -1.0));
+        assert_relative_eq!(comps.normalv, Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn computations_outside() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn computations_inside() {
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = Sphere::new();
+        let i = Intersection::new(1.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(comps.point, Point3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(comps.eyev, Vector3::new(0.0, 0.0, -1.0));
+        assert!(comps.inside);
+        // normal would have been (0, 0, 1), but is inverted
+        assert_relative_eq!(comps.normalv, Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn reflection_vector() {
+        use crate::shape::plane::Plane;
+
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+        let shape = Plane::new();
+        let r = Ray::new(Point3::new(0.0, 1.0, -1.0), Vector3::new(0.0, -k, k));
+        let i = Intersection::new(f32::sqrt(2.0), &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(comps.reflectv, Vector3::new(0.0, k, k));
+    }
+
+    #[test]
+    fn over_and_under_point() {
+        use nalgebra::Translation3;
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut shape = Sphere::new();
+        shape.set_transform(Translation3::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert!(comps.over_point.z < -EPSILON / 2.0);
+        assert!(comps.point.z > comps.over_point.z);
+        assert!(comps.under_point.z > EPSILON / 2.0);
+        assert!(comps.point.z < comps.under_point.z);
+    }
+
+    #[test]
+    fn refractive_indices() {
+        use nalgebra::{Scale3, Translation3};
+        use crate::shape::sphere::glass_sphere;
+
+        let mut a = glass_sphere();
+        a.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        a.material_mut().refractive_index = 1.5;
+        let mut b = glass_sphere();
+        b.set_transform(Translation3::new(0.0, 0.0, -0.25));
+        b.material_mut().refractive_index = 2.0;
+        let mut c = glass_sphere();
+        c.set_transform(Translation3::new(0.0, 0.0, 0.25));
+        c.material_mut().refractive_index = 2.5;
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -4.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ]);
+        let expected = [(1.0, 1.5), (1.5, 2.0), (2.0, 2.5), (2.5, 2.5), (2.5, 1.5), (1.5, 1.0)];
+        for (i, (n1, n2)) in xs.iter().zip(expected) {
+            let comps = i.prepare_computations(&r, &xs);
+            assert_relative_eq!(comps.n1, n1);
+            assert_relative_eq!(comps.n2, n2);
+        }
+    }
+
+    #[test]
+    fn schlick_total_internal_reflection() {
+        use crate::shape::sphere::glass_sphere;
+
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+        let shape = glass_sphere();
+        let r = Ray::new(Point3::new(0.0, 0.0, k), Vector3::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(-k, &shape), Intersection::new(k, &shape)]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_relative_eq!(comps.schlick(), 1.0);
+    }
+
+    #[test]
+    fn schlick_perpendicular() {
+        use crate::shape::sphere::glass_sphere;
+
+        let shape = glass_sphere();
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let xs = Intersections::new(vec![Intersection::new(-1.0, &shape), Intersection::new(1.0, &shape)]);
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_relative_eq!(comps.schlick(), 0.04, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn schlick_small_angle() {
+        use crate::shape::sphere::glass_sphere;
+
+        let shape = glass_sphere();
+        let r = Ray::new(Point3::new(0.0, 0.99, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![Intersection::new(1.8589, &shape)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_relative_eq!(comps.schlick(), 0.48873, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn collect_sorts() {
+        let s = Sphere::new();
+        let xs: Intersections = [4.0, -1.0, 2.0]
+            .into_iter()
+            .map(|t| Intersection::new(t, &s))
+            .collect();
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![-1.0, 2.0, 4.0]);
+    }
+}
@@ -1,5 +1,13 @@
+pub mod camera;
 pub mod canvas;
+pub mod intersection;
+pub mod light;
+pub mod material;
 pub mod math;
+pub mod obj;
+pub mod pattern;
+pub mod shape;
+pub mod world;
 
 #[cfg(test)]
 mod tests {
@@ -122,6 +130,21 @@ mod tests {
             assert_relative_eq!(v1.cross(&v2), Vector3::new(-1.0, 2.0, -1.0));
         }
 
+        #[test]
+        fn reflect_45_degrees() {
+            let v = Vector3::new(1.0, -1.0, 0.0);
+            let n = Vector3::new(0.0, 1.0, 0.0);
+            assert_relative_eq!(crate::math::reflect(&v, &n), Vector3::new(1.0, 1.0, 0.0));
+        }
+
+        #[test]
+        fn reflect_slanted() {
+            let k = std::f32::consts::FRAC_1_SQRT_2;
+            let v = Vector3::new(0.0, -1.0, 0.0);
+            let n = Vector3::new(k, k, 0.0);
+            assert_relative_eq!(crate::math::reflect(&v, &n), Vector3::new(1.0, 0.0, 0.0), epsilon = 1e-6);
+        }
+
         #[test]
         fn identity_matrix() {
             let mut i = Matrix3::identity();
@@ -357,7 +380,7 @@ mod tests {
         }
 
         mod chaining {
-            use nalgebra::{Affine3, Matrix3, Matrix4, Point3, Rotation3, Scale3, Translation3, Vector3};
+            use nalgebra::{Affine3, Point3, Rotation3, Scale3, Translation3, Vector3};
             use approx::assert_relative_eq;
             use crate::math::affine::Affine3Ext;
 
@@ -392,6 +415,63 @@ mod tests {
                 // Isn't this cool?  ^^^^^^^^^^^^^^^
             }
         }
+
+        mod view {
+            use nalgebra::{Affine3, Matrix4, Point3, Scale3, Translation3, Vector3};
+            use approx::assert_relative_eq;
+            use crate::math::affine::Affine3Ext;
+            use crate::math::view_transform;
+
+            #[test]
+            fn default_orientation() {
+                let from = Point3::new(0.0, 0.0, 0.0);
+                let to = Point3::new(0.0, 0.0, -1.0);
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                assert_relative_eq!(view_transform(&from, &to, &up).matrix(), Affine3::identity().matrix());
+            }
+
+            #[test]
+            fn looking_positive_z() {
+                let from = Point3::new(0.0, 0.0, 0.0);
+                let to = Point3::new(0.0, 0.0, 1.0);
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                // Looking backwards is the same as mirroring front to back and left to right
+                assert_relative_eq!(
+                    view_transform(&from, &to, &up).matrix(),
+                    Affine3::from_scale(Scale3::new(-1.0, 1.0, -1.0)).matrix()
+                );
+            }
+
+            #[test]
+            fn moves_the_world() {
+                let from = Point3::new(0.0, 0.0, 8.0);
+                let to = Point3::new(0.0, 0.0, 0.0);
+                let up = Vector3::new(0.0, 1.0, 0.0);
+                // The eye moves, so the world moves the other way
+                assert_relative_eq!(
+                    view_transform(&from, &to, &up).matrix(),
+                    &Translation3::new(0.0, 0.0, -8.0).to_homogeneous()
+                );
+            }
+
+            #[test]
+            fn arbitrary() {
+                let from = Point3::new(1.0, 3.0, 2.0);
+                let to = Point3::new(4.0, -2.0, 8.0);
+                let up = Vector3::new(1.0, 1.0, 0.0);
+                let t = view_transform(&from, &to, &up);
+
+                // The eye ends up at the origin, looking down -z
+                assert_relative_eq!(t * from, Point3::origin(), epsilon = 1e-5);
+                let forward = t * (to - from).normalize();
+                assert_relative_eq!(forward, Vector3::new(0.0, 0.0, -1.0), epsilon = 1e-5);
+
+                // and the rotation part stays orthonormal, so nothing gets stretched
+                let m: Matrix4<f32> = *t.matrix();
+                let r = m.fixed_view::<3, 3>(0, 0);
+                assert_relative_eq!(r * r.transpose(), nalgebra::Matrix3::identity(), epsilon = 1e-5);
+            }
+        }
     }
 }
 
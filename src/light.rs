@@ -0,0 +1,345 @@
+pub mod area;
+pub mod directional;
+pub mod spot;
+
+use std::fmt::Debug;
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::material::Material;
+use crate::math::reflect;
+use crate::shape::Shape;
+
+/// Something that lights a world.
+///
+/// A light is seen from a point as one or more samples. Lighting averages the samples'
+/// diffuse and specular terms, and shadows are the share of samples something is in the way of.
+pub trait Light: Debug + Send + Sync {
+    /// The color and brightness of the light, what the ambient term is made of
+    fn intensity(&self) -> Color;
+
+    /// The light as seen from `point`, never empty
+    fn samples(&self, point: &Point3<f32>) -> Vec<LightSample>;
+}
+
+/// Light arriving at a point from one direction
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct LightSample {
+    /// Normalized, from the point toward the light
+    pub direction: Vector3<f32>,
+    /// How far away the light is, infinite for lights like the sun
+    pub distance: f32,
+    /// How bright the light is by the time it gets there
+    pub intensity: Color,
+}
+
+impl LightSample {
+    /// A sample of light at `position`, seen from `point`
+    pub fn toward(point: &Point3<f32>, position: &Point3<f32>, intensity: Color) -> Self {
+        let v = position - point;
+        Self {
+            direction: v.normalize(),
+            distance: v.magnitude(),
+            intensity,
+        }
+    }
+}
+
+/// A light source with no size, shining equally in every direction.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PointLight {
+    pub position: Point3<f32>,
+    pub intensity: Color,
+}
+
+impl PointLight {
+    pub fn new(position: Point3<f32>, intensity: Color) -> Self {
+        Self { position, intensity }
+    }
+}
+
+impl Light for PointLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, point: &Point3<f32>) -> Vec<LightSample> {
+        vec![LightSample::toward(point, &self.position, self.intensity)]
+    }
+}
+
+/// Shade a point on a surface of `object` with the Phong reflection model.
+///
+/// `point` is in world space, the object is needed to find its pattern's color there.
+/// `eyev` points from `point` toward the eye and `normalv` is the surface normal, both normalized.
+/// `intensity` is the fraction of the light that reaches the point, from 0.0 in full shadow,
+/// where only the ambient term is left, to 1.0 in full light.
+///
+/// Lights with a size are sampled at several points, their diffuse and specular terms averaged.
+pub fn lighting(
+    material: &Material,
+    object: &dyn Shape,
+    light: &dyn Light,
+    point: &Point3<f32>,
+    eyev: &Vector3<f32>,
+    normalv: &Vector3<f32>,
+    intensity: f32,
+) -> Color {
+    let black = Color::new(0.0, 0.0, 0.0);
+
+    let color = match &material.pattern {
+        Some(pattern) => pattern.pattern_at_shape(object, point),
+        None => material.color,
+    };
+
+    // Blend the surface color with the light's color
+    let ambient = color * light.intensity() * material.ambient;
+    if intensity <= 0.0 {
+        return ambient;
+    }
+
+    let samples = light.samples(point);
+    if samples.is_empty() {
+        return ambient;
+    }
+    let mut sum = black;
+    for sample in &samples {
+        let lightv = sample.direction;
+
+        // A negative cosine between the light and the normal means the light is behind the surface
+        let light_dot_normal = lightv.dot(normalv);
+        if light_dot_normal < 0.0 {
+            continue;
+        }
+        sum = sum + color * sample.intensity * material.diffuse * light_dot_normal;
+
+        // A negative cosine between the reflection and the eye means the reflection points away from the eye
+        let reflectv = reflect(&-lightv, normalv);
+        let reflect_dot_eye = reflectv.dot(eyev);
+        if reflect_dot_eye > 0.0 {
+            let factor = reflect_dot_eye.powf(material.shininess);
+            sum = sum + sample.intensity * material.specular * factor;
+        }
+    }
+
+    ambient + sum * (intensity / samples.len() as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn point_light() {
+        let intensity = Color::new(1.0, 1.0, 1.0);
+        let position = Point3::new(0.0, 0.0, 0.0);
+        let light = PointLight::new(position, intensity);
+        assert_relative_eq!(light.position, position);
+        assert_relative_eq!(light.intensity, intensity);
+    }
+
+    #[test]
+    fn point_light_sample() {
+        let light = PointLight::new(Point3::new(0.0, 10.0, 0.0), Color::new(1.0, 0.5, 1.0));
+        let samples = light.samples(&Point3::new(0.0, 2.0, 0.0));
+        assert_eq!(samples.len(), 1);
+        assert_relative_eq!(samples[0].direction, Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(samples[0].distance, 8.0);
+        assert_relative_eq!(samples[0].intensity, light.intensity);
+    }
+
+    mod lighting {
+        use super::*;
+        use crate::light::area::AreaLight;
+        use crate::shape::sphere::Sphere;
+
+        #[test]
+        fn partial_intensity() {
+            let m = Material {
+                ambient: 0.1,
+                diffuse: 0.9,
+                specular: 0.0,
+                color: Color::new(1.0, 1.0, 1.0),
+                ..Material::default()
+            };
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let point = Point3::new(0.0, 0.0, -1.0);
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            for (intensity, expected) in [(1.0, 1.0), (0.5, 0.55), (0.0, 0.1)] {
+                let result = lighting(&m, &Sphere::new(), &light, &point, &eyev, &normalv, intensity);
+                assert_relative_eq!(result, Color::new(expected, expected, expected), epsilon = 1e-4);
+            }
+        }
+
+        #[test]
+        fn samples_area_light() {
+            let light = AreaLight::new(
+                Point3::new(-0.5, -0.5, -5.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                2,
+                Vector3::new(0.0, 1.0, 0.0),
+                2,
+                Color::new(1.0, 1.0, 1.0),
+            );
+            let m = Material {
+                ambient: 0.1,
+                diffuse: 0.9,
+                specular: 0.0,
+                color: Color::new(1.0, 1.0, 1.0),
+                ..Material::default()
+            };
+            let eye = Point3::new(0.0, 0.0, -5.0);
+            let k = std::f32::consts::FRAC_1_SQRT_2;
+            for (point, expected) in [(Point3::new(0.0, 0.0, -1.0), 0.9965), (Point3::new(0.0, k, -k), 0.62318)] {
+                let eyev = (eye - point).normalize();
+                let normalv = point.coords;
+                let result = lighting(&m, &Sphere::new(), &light, &point, &eyev, &normalv, 1.0);
+                assert_relative_eq!(result, Color::new(expected, expected, expected), epsilon = 1e-4);
+            }
+        }
+
+        #[test]
+        fn light_without_samples() {
+            let mut light = AreaLight::new(
+                Point3::new(-0.5, -0.5, -5.0),
+                Vector3::new(1.0, 0.0, 0.0),
+                2,
+                Vector3::new(0.0, 1.0, 0.0),
+                2,
+                Color::new(1.0, 1.0, 1.0),
+            );
+            light.vsteps = 0;
+            let point = Point3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let result = lighting(&Material::default(), &Sphere::new(), &light, &point, &normalv, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(0.1, 0.1, 0.1), epsilon = 1e-4);
+        }
+
+        fn setup() -> (Material, Point3<f32>) {
+            (Material::default(), Point3::origin())
+        }
+
+        #[test]
+        fn eye_between_light_and_surface() {
+            let (m, position) = setup();
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(1.9, 1.9, 1.9), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn eye_offset_45_degrees() {
+            let (m, position) = setup();
+            let k = std::f32::consts::FRAC_1_SQRT_2;
+            let eyev = Vector3::new(0.0, k, -k);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(1.0, 1.0, 1.0), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn light_offset_45_degrees() {
+            let (m, position) = setup();
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(0.7364, 0.7364, 0.7364), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn eye_in_reflection_path() {
+            let (m, position) = setup();
+            let k = std::f32::consts::FRAC_1_SQRT_2;
+            let eyev = Vector3::new(0.0, -k, -k);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(1.6364, 1.6364, 1.6364), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn light_behind_surface() {
+            let (m, position) = setup();
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 1.0);
+            assert_relative_eq!(result, Color::new(0.1, 0.1, 0.1), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn surface_in_shadow() {
+            let (m, position) = setup();
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &position, &eyev, &normalv, 0.0);
+            assert_relative_eq!(result, Color::new(0.1, 0.1, 0.1), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn colored_light_and_surface() {
+            let m = Material {
+                color: Color::new(1.0, 0.5, 0.0),
+                ..Material::default()
+            };
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(0.5, 0.5, 1.0));
+            let result = lighting(&m, &Sphere::new(), &light, &Point3::origin(), &eyev, &normalv, 1.0);
+            // ambient + diffuse use the blended color, the highlight only the light's
+            assert_relative_eq!(result, Color::new(0.95, 0.7, 0.9), epsilon = 1e-4);
+        }
+
+        #[test]
+        fn with_pattern() {
+            use crate::pattern::stripe::Stripe;
+
+            let white = Color::new(1.0, 1.0, 1.0);
+            let black = Color::new(0.0, 0.0, 0.0);
+            let mut m = Material {
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            };
+            m.set_pattern(Stripe::new(white, black));
+            let object = Sphere::new();
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), white);
+            let c1 = lighting(&m, &object, &light, &Point3::new(0.9, 0.0, 0.0), &eyev, &normalv, 1.0);
+            let c2 = lighting(&m, &object, &light, &Point3::new(1.1, 0.0, 0.0), &eyev, &normalv, 1.0);
+            assert_relative_eq!(c1, white);
+            assert_relative_eq!(c2, black);
+        }
+
+        #[test]
+        fn pattern_follows_object() {
+            use nalgebra::Translation3;
+            use crate::pattern::stripe::Stripe;
+
+            let white = Color::new(1.0, 1.0, 1.0);
+            let black = Color::new(0.0, 0.0, 0.0);
+            let mut m = Material {
+                ambient: 1.0,
+                diffuse: 0.0,
+                specular: 0.0,
+                ..Material::default()
+            };
+            m.set_pattern(Stripe::new(white, black));
+            let mut object = Sphere::new();
+            object.set_transform(Translation3::new(1.0, 0.0, 0.0));
+            let eyev = Vector3::new(0.0, 0.0, -1.0);
+            let normalv = Vector3::new(0.0, 0.0, -1.0);
+            let light = PointLight::new(Point3::new(0.0, 0.0, -10.0), white);
+            let c = lighting(&m, &object, &light, &Point3::new(1.1, 0.0, 0.0), &eyev, &normalv, 1.0);
+            assert_relative_eq!(c, white);
+        }
+    }
+}
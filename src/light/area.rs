@@ -0,0 +1,192 @@
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::light::{Light, LightSample};
+use crate::math::hash;
+
+/// A flat, rectangular light, from `corner` along the edges `uvec` and `vvec`.
+///
+/// It is sampled as a grid of `usteps` by `vsteps` cells, one sample per cell. The fraction
+/// of samples a point can see decides how deep in shadow it is, which gives shadows soft edges.
+/// Samples are at the center of their cells unless `jitter` is on, which moves each one
+/// somewhere random inside its cell to trade the banding of a regular grid for noise.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AreaLight {
+    pub corner: Point3<f32>,
+    pub uvec: Vector3<f32>,
+    pub usteps: usize,
+    pub vvec: Vector3<f32>,
+    pub vsteps: usize,
+    pub intensity: Color,
+    pub jitter: bool,
+}
+
+impl AreaLight {
+    /// # Panics
+    ///
+    /// If either step count is zero, the light would have no samples to average over.
+    pub fn new(
+        corner: Point3<f32>,
+        uvec: Vector3<f32>,
+        usteps: usize,
+        vvec: Vector3<f32>,
+        vsteps: usize,
+        intensity: Color,
+    ) -> Self {
+        assert!(usteps >= 1 && vsteps >= 1, "an area light needs at least one step each way");
+        Self {
+            corner,
+            uvec,
+            usteps,
+            vvec,
+            vsteps,
+            intensity,
+            jitter: false,
+        }
+    }
+
+    /// The same light with its samples jittered
+    pub fn jittered(self) -> Self {
+        Self { jitter: true, ..self }
+    }
+
+    /// The center of the light
+    pub fn position(&self) -> Point3<f32> {
+        self.corner + (self.uvec + self.vvec) / 2.0
+    }
+
+    /// How many samples the light is made of
+    pub fn samples(&self) -> usize {
+        self.usteps * self.vsteps
+    }
+
+    /// A point in the cell at (`u`, `v`), `offset` is where in the cell, from 0.0 to 1.0 each way
+    pub fn point_on_light(&self, u: usize, v: usize, offset: (f32, f32)) -> Point3<f32> {
+        self.corner
+            + self.uvec * ((u as f32 + offset.0) / self.usteps as f32)
+            + self.vvec * ((v as f32 + offset.1) / self.vsteps as f32)
+    }
+
+    /// Every sample of the light, as seen from `point`.
+    ///
+    /// The jitter depends on the point being lit, so neighbouring points see different samples
+    /// but the same point always sees the same ones.
+    pub fn sample_points(&self, point: &Point3<f32>) -> Vec<Point3<f32>> {
+        let seed = hash(
+            point.x.to_bits() as i32,
+            point.y.to_bits() as i32,
+            point.z.to_bits() as i32,
+        ) as i32;
+
+        let mut samples = Vec::with_capacity(self.samples());
+        for v in 0..self.vsteps {
+            for u in 0..self.usteps {
+                let offset = if self.jitter {
+                    let h = hash(seed, u as i32, v as i32);
+                    ((h & 0xffff) as f32 / 65536.0, (h >> 16) as f32 / 65536.0)
+                } else {
+                    (0.5, 0.5)
+                };
+                samples.push(self.point_on_light(u, v, offset));
+            }
+        }
+        samples
+    }
+}
+
+impl Light for AreaLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, point: &Point3<f32>) -> Vec<LightSample> {
+        self.sample_points(point)
+            .iter()
+            .map(|sample| LightSample::toward(point, sample, self.intensity))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn test_light() -> AreaLight {
+        AreaLight::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            4,
+            Vector3::new(0.0, 0.0, 1.0),
+            2,
+            Color::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn new() {
+        let light = test_light();
+        assert_eq!(light.samples(), 8);
+        assert_relative_eq!(light.position(), Point3::new(1.0, 0.0, 0.5));
+        assert!(!light.jitter);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one step")]
+    fn zero_steps() {
+        AreaLight::new(
+            Point3::new(0.0, 0.0, 0.0),
+            Vector3::new(2.0, 0.0, 0.0),
+            0,
+            Vector3::new(0.0, 0.0, 1.0),
+            2,
+            Color::new(1.0, 1.0, 1.0),
+        );
+    }
+
+    #[test]
+    fn point_on_light() {
+        let light = test_light();
+        let cases = [
+            (0, 0, Point3::new(0.25, 0.0, 0.25)),
+            (1, 0, Point3::new(0.75, 0.0, 0.25)),
+            (0, 1, Point3::new(0.25, 0.0, 0.75)),
+            (2, 0, Point3::new(1.25, 0.0, 0.25)),
+            (3, 1, Point3::new(1.75, 0.0, 0.75)),
+        ];
+        for (u, v, expected) in cases {
+            assert_relative_eq!(light.point_on_light(u, v, (0.5, 0.5)), expected);
+        }
+    }
+
+    #[test]
+    fn point_on_light_offset() {
+        let light = test_light();
+        assert_relative_eq!(light.point_on_light(0, 0, (0.3, 0.7)), Point3::new(0.15, 0.0, 0.35));
+        assert_relative_eq!(light.point_on_light(3, 1, (0.3, 0.7)), Point3::new(1.65, 0.0, 0.85));
+    }
+
+    #[test]
+    fn samples_are_cell_centers() {
+        let light = test_light();
+        let samples = light.sample_points(&Point3::new(0.0, 5.0, 0.0));
+        assert_eq!(samples.len(), 8);
+        assert_relative_eq!(samples[0], Point3::new(0.25, 0.0, 0.25));
+        assert_relative_eq!(samples[7], Point3::new(1.75, 0.0, 0.75));
+    }
+
+    #[test]
+    fn jittered_samples_stay_in_their_cells() {
+        let light = test_light().jittered();
+        let point = Point3::new(0.3, 5.0, -2.0);
+        let samples = light.sample_points(&point);
+        for (i, sample) in samples.iter().enumerate() {
+            let (u, v) = ((i % 4) as f32, (i / 4) as f32);
+            assert!((u * 0.5..=(u + 1.0) * 0.5).contains(&sample.x), "{sample:?}");
+            assert!((v * 0.5..=(v + 1.0) * 0.5).contains(&sample.z), "{sample:?}");
+        }
+        // Repeatable for the same point, different for another
+        assert_eq!(samples, light.sample_points(&point));
+        assert_ne!(samples, light.sample_points(&Point3::new(0.4, 5.0, -2.0)));
+        assert_ne!(samples, test_light().sample_points(&point));
+    }
+}
@@ -0,0 +1,60 @@
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::light::{Light, LightSample};
+
+/// A light infinitely far away, like the sun: every ray of it is parallel and nothing is
+/// ever behind it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct DirectionalLight {
+    /// Normalized, the way the light travels
+    pub direction: Vector3<f32>,
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    /// A light shining along `direction`, which doesn't need to be normalized
+    pub fn new(direction: Vector3<f32>, intensity: Color) -> Self {
+        Self {
+            direction: direction.normalize(),
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, _point: &Point3<f32>) -> Vec<LightSample> {
+        vec![LightSample {
+            direction: -self.direction,
+            distance: f32::INFINITY,
+            intensity: self.intensity,
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn new_normalizes() {
+        let light = DirectionalLight::new(Vector3::new(0.0, -2.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        assert_relative_eq!(light.direction, Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn same_everywhere() {
+        let light = DirectionalLight::new(Vector3::new(1.0, -1.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+        for point in [Point3::origin(), Point3::new(100.0, -50.0, 3.0)] {
+            let samples = light.samples(&point);
+            assert_eq!(samples.len(), 1);
+            assert_relative_eq!(samples[0].direction, Vector3::new(-k, k, 0.0));
+            assert_eq!(samples[0].distance, f32::INFINITY);
+        }
+    }
+}
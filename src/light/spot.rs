@@ -0,0 +1,129 @@
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::light::{Light, LightSample};
+
+/// A point light that only shines inside a cone, like a stage light.
+///
+/// Within `inner_angle` of `direction` it is at full brightness, past `outer_angle` it is dark,
+/// and in between it fades out smoothly. Both angles are in radians, measured from the axis.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct SpotLight {
+    pub position: Point3<f32>,
+    /// Normalized, the axis of the cone
+    pub direction: Vector3<f32>,
+    pub inner_angle: f32,
+    pub outer_angle: f32,
+    pub intensity: Color,
+}
+
+impl SpotLight {
+    /// A spot at `position` pointing along `direction`, which doesn't need to be normalized
+    pub fn new(
+        position: Point3<f32>,
+        direction: Vector3<f32>,
+        inner_angle: f32,
+        outer_angle: f32,
+        intensity: Color,
+    ) -> Self {
+        Self {
+            position,
+            direction: direction.normalize(),
+            inner_angle,
+            outer_angle,
+            intensity,
+        }
+    }
+
+    /// How much of the light reaches `point`, from 0.0 outside the cone to 1.0 inside the inner one.
+    ///
+    /// A point right at the light has no direction from it, it counts as inside the inner cone.
+    pub fn falloff(&self, point: &Point3<f32>) -> f32 {
+        let Some(to_point) = (point - self.position).try_normalize(0.0) else {
+            return 1.0;
+        };
+        let cos = to_point.dot(&self.direction);
+        let (cos_outer, cos_inner) = (self.outer_angle.cos(), self.inner_angle.cos());
+        if cos >= cos_inner {
+            1.0
+        } else if cos <= cos_outer {
+            0.0
+        } else {
+            // Smoothstep, so the edge of the pool of light has no visible ring
+            let t = (cos - cos_outer) / (cos_inner - cos_outer);
+            t * t * (3.0 - 2.0 * t)
+        }
+    }
+}
+
+impl Light for SpotLight {
+    fn intensity(&self) -> Color {
+        self.intensity
+    }
+
+    fn samples(&self, point: &Point3<f32>) -> Vec<LightSample> {
+        let intensity = self.intensity * self.falloff(point);
+        vec![LightSample::toward(point, &self.position, intensity)]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_4;
+    use approx::assert_relative_eq;
+
+    fn test_light() -> SpotLight {
+        // Pointing straight down, full brightness up to 30 degrees, dark from 45
+        SpotLight::new(
+            Point3::new(0.0, 10.0, 0.0),
+            Vector3::new(0.0, -2.0, 0.0),
+            30f32.to_radians(),
+            FRAC_PI_4,
+            Color::new(1.0, 1.0, 1.0),
+        )
+    }
+
+    #[test]
+    fn new_normalizes() {
+        assert_relative_eq!(test_light().direction, Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn falloff() {
+        let light = test_light();
+        // Straight below, inside the inner cone
+        assert_relative_eq!(light.falloff(&Point3::new(0.0, 0.0, 0.0)), 1.0);
+        assert_relative_eq!(light.falloff(&Point3::new(5.0, 0.0, 0.0)), 1.0);
+        // Past 45 degrees, and behind the light
+        assert_relative_eq!(light.falloff(&Point3::new(11.0, 0.0, 0.0)), 0.0);
+        assert_relative_eq!(light.falloff(&Point3::new(0.0, 20.0, 0.0)), 0.0);
+        // At the light itself
+        assert_relative_eq!(light.falloff(&light.position), 1.0);
+    }
+
+    #[test]
+    fn falloff_is_smooth() {
+        let light = test_light();
+        let mut previous = 1.0;
+        // Walk out from the inner to the outer cone, the light only ever gets dimmer
+        for i in 0..=20 {
+            let x = 10.0 * (30.0 + 15.0 * i as f32 / 20.0).to_radians().tan();
+            let f = light.falloff(&Point3::new(x, 0.0, 0.0));
+            assert!((0.0..=previous).contains(&f), "{i}: {f}");
+            previous = f;
+        }
+        assert_relative_eq!(previous, 0.0, epsilon = 1e-4);
+
+        let halfway = 10.0 * 37.5f32.to_radians().tan();
+        let f = light.falloff(&Point3::new(halfway, 0.0, 0.0));
+        assert!(0.3 < f && f < 0.7, "{f}");
+    }
+
+    #[test]
+    fn samples_are_dimmed() {
+        let light = test_light();
+        let samples = light.samples(&Point3::new(11.0, 0.0, 0.0));
+        assert_relative_eq!(samples[0].intensity, Color::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(samples[0].distance, f32::hypot(11.0, 10.0));
+    }
+}
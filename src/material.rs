@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use crate::canvas::color::Color;
+use crate::pattern::{IntoPattern, Pattern};
+
+/// Surface properties of a shape, as used by the Phong reflection model.
+///
+/// `ambient`, `diffuse` and `specular` are the weights of each lighting term, usually
+/// between 0 and 1. `shininess` is the specular exponent: the higher it is, the smaller
+/// and tighter the highlight.
+///
+/// When a `pattern` is set it replaces the flat `color`.
+/// `reflective` is how much of the surrounding scene the surface mirrors: 0.0 is matte, 1.0 a perfect mirror.
+/// `transparency` works the same way for light passing through, bent by `refractive_index`
+/// (1.0 vacuum, 1.333 water, 1.52 glass, 2.417 diamond).
+#[derive(Debug, Clone)]
+pub struct Material {
+    pub color: Color,
+    pub pattern: Option<Arc<dyn Pattern>>,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+    pub reflective: f32,
+    pub transparency: f32,
+    pub refractive_index: f32,
+}
+
+impl Material {
+    pub fn set_pattern(&mut self, pattern: impl IntoPattern) {
+        self.pattern = Some(pattern.into_pattern());
+    }
+}
+
+impl Default for Material {
+    /// A plain white, fairly glossy surface
+    fn default() -> Self {
+        Self {
+            color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
+            ambient: 0.1,
+            diffuse: 0.9,
+            specular: 0.9,
+            shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn default() {
+        let m = Material::default();
+        assert_relative_eq!(m.color, Color::new(1.0, 1.0, 1.0));
+        assert!(m.pattern.is_none());
+        assert_relative_eq!(m.ambient, 0.1);
+        assert_relative_eq!(m.diffuse, 0.9);
+        assert_relative_eq!(m.specular, 0.9);
+        assert_relative_eq!(m.shininess, 200.0);
+        assert_relative_eq!(m.reflective, 0.0);
+        assert_relative_eq!(m.transparency, 0.0);
+        assert_relative_eq!(m.refractive_index, 1.0);
+    }
+}
@@ -1,3 +1,50 @@
+pub mod aabb;
+pub mod bvh;
+pub mod ray;
+
+/// Tolerance for floating point comparisons in geometry.
+///
+/// Also the distance hit points are nudged off a surface, so rays cast from them don't
+/// immediately hit that same surface again because of rounding ("acne").
+pub const EPSILON: f32 = 1e-4;
+
+use nalgebra::{Affine3, Matrix4, Point3, Translation3, Vector3};
+
+/// Scramble three integers into a pseudo random, but repeatable, number
+pub(crate) fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut h = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h
+}
+
+/// Reflect `v` around `normal`
+pub fn reflect(v: &Vector3<f32>, normal: &Vector3<f32>) -> Vector3<f32> {
+    v - normal * 2.0 * v.dot(normal)
+}
+
+/// The transformation that orients the world relative to an eye at `from`, looking at `to`.
+///
+/// `up` only needs to point roughly upward, the true up vector is recomputed from it.
+/// After the transform the eye sits at the origin looking down -z, with +y up.
+pub fn view_transform(from: &Point3<f32>, to: &Point3<f32>, up: &Vector3<f32>) -> Affine3<f32> {
+    let forward = (to - from).normalize();
+    let left = forward.cross(&up.normalize()).normalize();
+    let true_up = left.cross(&forward);
+
+    let orientation = Matrix4::new(
+        left.x, left.y, left.z, 0.0,
+        true_up.x, true_up.y, true_up.z, 0.0,
+        -forward.x, -forward.y, -forward.z, 0.0,
+        0.0, 0.0, 0.0, 1.0,
+    );
+    let translation = Translation3::new(-from.x, -from.y, -from.z).to_homogeneous();
+    Affine3::from_matrix_unchecked(orientation * translation)
+}
+
 pub mod affine {
 
     // Transformations are algebraic entities that act on points and vectors.
@@ -14,7 +61,7 @@ pub mod affine {
     // You can then use the Affine object in transformation chaining (matrix mult under the hood).
     // e.g., (Affine * Rotation * Translation) * Vector
 
-    use nalgebra::{Affine3, Matrix4, RealField, Scale3};
+    use nalgebra::{Affine3, Isometry3, IsometryMatrix3, Matrix4, RealField, Rotation3, Scale3, Translation3, UnitQuaternion};
 
     pub trait Affine3Ext<T: RealField> {
         fn from_shear(xy: T, xz: T, yx: T, yz: T, zx: T, zy: T) -> Self;
@@ -42,5 +89,53 @@ pub mod affine {
             Affine3::from_matrix_unchecked(scale.to_homogeneous())
         }
     }
-}
 
+    // Rays and shapes want "any transformation", but nalgebra keeps Translation, Rotation, Scale
+    // and Affine as separate types. Every one of them has a homogeneous matrix though,
+    // so viewing them all as an Affine3 is just a matter of wrapping that matrix.
+    pub trait ToAffine3<T: RealField> {
+        fn to_affine3(&self) -> Affine3<T>;
+    }
+
+    impl<T: RealField> ToAffine3<T> for Affine3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            self.clone()
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for Translation3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_matrix_unchecked(self.to_homogeneous())
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for Rotation3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_matrix_unchecked(self.to_homogeneous())
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for UnitQuaternion<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_matrix_unchecked(self.clone().to_homogeneous())
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for Isometry3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_matrix_unchecked(self.to_homogeneous())
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for IsometryMatrix3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_matrix_unchecked(self.to_homogeneous())
+        }
+    }
+
+    impl<T: RealField> ToAffine3<T> for Scale3<T> {
+        fn to_affine3(&self) -> Affine3<T> {
+            Affine3::from_scale(self.clone())
+        }
+    }
+}
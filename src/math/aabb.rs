@@ -0,0 +1,317 @@
+use nalgebra::Point3;
+use crate::math::affine::ToAffine3;
+use crate::math::ray::Ray;
+
+/// An axis-aligned bounding box, from `min` to `max` on every axis.
+///
+/// The empty box has `min` at +infinity and `max` at -infinity, so adding the first point
+/// or box to it just takes that point or box over. Unbounded shapes like planes have
+/// infinite extents.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>,
+}
+
+impl Aabb {
+    pub fn new(min: Point3<f32>, max: Point3<f32>) -> Self {
+        Self { min, max }
+    }
+
+    /// A box containing nothing
+    pub fn empty() -> Self {
+        Self {
+            min: Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+            max: Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+
+    /// A box containing everything
+    pub fn infinite() -> Self {
+        Self {
+            min: Point3::new(f32::NEG_INFINITY, f32::NEG_INFINITY, f32::NEG_INFINITY),
+            max: Point3::new(f32::INFINITY, f32::INFINITY, f32::INFINITY),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// Are all the extents finite numbers? Unbounded shapes are not
+    pub fn is_finite(&self) -> bool {
+        self.min.iter().chain(self.max.iter()).all(|x| x.is_finite())
+    }
+
+    /// Grow the box to include `point`
+    pub fn add_point(&mut self, point: &Point3<f32>) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    /// Grow the box to include `other`
+    pub fn add_box(&mut self, other: &Aabb) {
+        self.min = self.min.inf(&other.min);
+        self.max = self.max.sup(&other.max);
+    }
+
+    /// The smallest box containing both boxes
+    pub fn merge(&self, other: &Aabb) -> Aabb {
+        let mut merged = *self;
+        merged.add_box(other);
+        merged
+    }
+
+    pub fn contains_point(&self, point: &Point3<f32>) -> bool {
+        (0..3).all(|i| self.min[i] <= point[i] && point[i] <= self.max[i])
+    }
+
+    pub fn contains_box(&self, other: &Aabb) -> bool {
+        self.contains_point(&other.min) && self.contains_point(&other.max)
+    }
+
+    pub fn centroid(&self) -> Point3<f32> {
+        nalgebra::center(&self.min, &self.max)
+    }
+
+    /// Total area of the six faces, what the surface area heuristic weighs splits by
+    pub fn surface_area(&self) -> f32 {
+        if self.is_empty() {
+            return 0.0;
+        }
+        let d = self.max - self.min;
+        2.0 * (d.x * d.y + d.y * d.z + d.z * d.x)
+    }
+
+    /// The axis the box is longest along: 0 for x, 1 for y, 2 for z
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x >= d.y && d.x >= d.z {
+            0
+        } else if d.y >= d.z {
+            1
+        } else {
+            2
+        }
+    }
+
+    /// The box around this box after a transformation.
+    ///
+    /// Rotations and shears tilt the box, so all eight corners are transformed and
+    /// a new axis-aligned box is fit around them. Boxes with infinite extents stay infinite,
+    /// the corners of those are not meaningful points.
+    pub fn transform<M: ToAffine3<f32>>(&self, m: &M) -> Aabb {
+        if self.is_empty() {
+            return *self;
+        }
+        if !self.is_finite() {
+            return Aabb::infinite();
+        }
+
+        let m = m.to_affine3();
+        let mut transformed = Aabb::empty();
+        for x in [self.min.x, self.max.x] {
+            for y in [self.min.y, self.max.y] {
+                for z in [self.min.z, self.max.z] {
+                    transformed.add_point(&(m * Point3::new(x, y, z)));
+                }
+            }
+        }
+        transformed
+    }
+
+    /// The `t` values where the ray enters and leaves the box, if it goes through it at all.
+    ///
+    /// Rays running exactly along a face or an edge count as going through.
+    pub fn intersect(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let (xtmin, xtmax) = check_axis(ray.origin.x, ray.direction.x, self.min.x, self.max.x);
+        let (ytmin, ytmax) = check_axis(ray.origin.y, ray.direction.y, self.min.y, self.max.y);
+        let (ztmin, ztmax) = check_axis(ray.origin.z, ray.direction.z, self.min.z, self.max.z);
+
+        // Inside the box between the last slab the ray enters and the first one it leaves
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        if tmin > tmax {
+            None
+        } else {
+            Some((tmin, tmax))
+        }
+    }
+
+    pub fn intersects(&self, ray: &Ray) -> bool {
+        self.intersect(ray).is_some()
+    }
+}
+
+impl Default for Aabb {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+/// The `t` values where a ray enters and leaves the slab between `min` and `max` along one axis.
+fn check_axis(origin: f32, direction: f32, min: f32, max: f32) -> (f32, f32) {
+    // Only exactly parallel rays need care. Tiny directions are fine to divide by, and are all an object space
+    // ray has left once a box is scaled up, so an epsilon here would cull rays that clearly hit.
+    if direction == 0.0 {
+        // Parallel to the slab: the ray is either always between its planes or never.
+        // A ray running exactly along a face counts as inside, so grazing rays still hit.
+        return if (min..=max).contains(&origin) {
+            (f32::NEG_INFINITY, f32::INFINITY)
+        } else {
+            (f32::INFINITY, f32::NEG_INFINITY)
+        };
+    }
+
+    let t1 = (min - origin) / direction;
+    let t2 = (max - origin) / direction;
+    if t1 > t2 {
+        (t2, t1)
+    } else {
+        (t1, t2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_4, SQRT_2};
+    use approx::assert_relative_eq;
+    use nalgebra::{Rotation3, Scale3, Translation3, Vector3};
+
+    #[test]
+    fn empty() {
+        let b = Aabb::empty();
+        assert!(b.is_empty());
+        assert_relative_eq!(b.surface_area(), 0.0);
+    }
+
+    #[test]
+    fn add_points() {
+        let mut b = Aabb::empty();
+        b.add_point(&Point3::new(-5.0, 2.0, 0.0));
+        b.add_point(&Point3::new(7.0, 0.0, -3.0));
+        assert_relative_eq!(b.min, Point3::new(-5.0, 0.0, -3.0));
+        assert_relative_eq!(b.max, Point3::new(7.0, 2.0, 0.0));
+    }
+
+    #[test]
+    fn merge() {
+        let a = Aabb::new(Point3::new(-5.0, -2.0, 0.0), Point3::new(7.0, 4.0, 4.0));
+        let b = Aabb::new(Point3::new(8.0, -7.0, -2.0), Point3::new(14.0, 2.0, 8.0));
+        let m = a.merge(&b);
+        assert_relative_eq!(m.min, Point3::new(-5.0, -7.0, -2.0));
+        assert_relative_eq!(m.max, Point3::new(14.0, 4.0, 8.0));
+    }
+
+    #[test]
+    fn contains() {
+        let b = Aabb::new(Point3::new(5.0, -2.0, 0.0), Point3::new(11.0, 4.0, 7.0));
+        assert!(b.contains_point(&Point3::new(5.0, -2.0, 0.0)));
+        assert!(b.contains_point(&Point3::new(11.0, 4.0, 7.0)));
+        assert!(b.contains_point(&Point3::new(8.0, 1.0, 3.0)));
+        assert!(!b.contains_point(&Point3::new(3.0, 0.0, 3.0)));
+        assert!(!b.contains_point(&Point3::new(8.0, -4.0, 3.0)));
+        assert!(!b.contains_point(&Point3::new(8.0, 1.0, 8.0)));
+
+        assert!(b.contains_box(&Aabb::new(Point3::new(6.0, -1.0, 1.0), Point3::new(10.0, 3.0, 6.0))));
+        assert!(!b.contains_box(&Aabb::new(Point3::new(4.0, -3.0, -1.0), Point3::new(10.0, 3.0, 6.0))));
+    }
+
+    #[test]
+    fn surface_area() {
+        let b = Aabb::new(Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 2.0, 3.0));
+        assert_relative_eq!(b.surface_area(), 22.0);
+        assert_eq!(b.longest_axis(), 2);
+        assert_relative_eq!(b.centroid(), Point3::new(0.5, 1.0, 1.5));
+    }
+
+    #[test]
+    fn transform_all_corners() {
+        // A rotated cube pokes out further than its corners on the axes would suggest
+        let b = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let m = Rotation3::from_axis_angle(&Vector3::x_axis(), FRAC_PI_4)
+            * Rotation3::from_axis_angle(&Vector3::y_axis(), FRAC_PI_4);
+        let t = b.transform(&m);
+        assert_relative_eq!(t.min, Point3::new(-SQRT_2, -1.70711, -1.70711), epsilon = 1e-4);
+        assert_relative_eq!(t.max, Point3::new(SQRT_2, 1.70711, 1.70711), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn transform_scaled_translated() {
+        let b = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let t = b.transform(&(Translation3::new(1.0, 2.0, 3.0) * Rotation3::identity()));
+        assert_relative_eq!(t.min, Point3::new(0.0, 1.0, 2.0));
+        let t = b.transform(&Scale3::new(2.0, 3.0, 4.0));
+        assert_relative_eq!(t.max, Point3::new(2.0, 3.0, 4.0));
+    }
+
+    #[test]
+    fn transform_infinite() {
+        let plane = Aabb::new(
+            Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+            Point3::new(f32::INFINITY, 0.0, f32::INFINITY),
+        );
+        let t = plane.transform(&Rotation3::from_axis_angle(&Vector3::x_axis(), FRAC_PI_4));
+        assert_eq!(t, Aabb::infinite());
+        assert!(!t.is_finite());
+    }
+
+    #[test]
+    fn intersects_cube() {
+        let b = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let cases = [
+            (Point3::new(5.0, 0.5, 0.0), Vector3::new(-1.0, 0.0, 0.0), true),
+            (Point3::new(-5.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0), true),
+            (Point3::new(0.5, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), true),
+            (Point3::new(0.5, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0), true),
+            (Point3::new(0.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), true),
+            (Point3::new(0.5, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), true),
+            (Point3::new(0.0, 0.5, 0.0), Vector3::new(0.0, 0.0, 1.0), true),
+            (Point3::new(-2.0, 0.0, 0.0), Vector3::new(2.0, 4.0, 6.0), false),
+            (Point3::new(0.0, -2.0, 0.0), Vector3::new(6.0, 2.0, 4.0), false),
+            (Point3::new(0.0, 0.0, -2.0), Vector3::new(4.0, 6.0, 2.0), false),
+            (Point3::new(2.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0), false),
+            (Point3::new(0.0, 2.0, 2.0), Vector3::new(0.0, -1.0, 0.0), false),
+            (Point3::new(2.0, 2.0, 0.0), Vector3::new(-1.0, 0.0, 0.0), false),
+        ];
+        for (origin, direction, hit) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), hit, "{origin:?} {direction:?}");
+        }
+    }
+
+    #[test]
+    fn tiny_directions_still_hit() {
+        // What's left of a ray's direction in the object space of a box scaled up 10000 times
+        let b = Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0));
+        let r = Ray::new(Point3::new(0.5, 2.0, -2.0), Vector3::new(0.0, -0.5e-4, 0.866e-4));
+        let (tmin, tmax) = b.intersect(&r).unwrap();
+        assert_relative_eq!(tmin, 20000.0, max_relative = 1e-4);
+        assert_relative_eq!(tmax, 34642.0, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn intersects_non_cubic() {
+        let b = Aabb::new(Point3::new(5.0, -2.0, 0.0), Point3::new(11.0, 4.0, 7.0));
+        let cases = [
+            (Point3::new(15.0, 1.0, 2.0), Vector3::new(-1.0, 0.0, 0.0), true),
+            (Point3::new(-5.0, -1.0, 4.0), Vector3::new(1.0, 0.0, 0.0), true),
+            (Point3::new(7.0, 6.0, 5.0), Vector3::new(0.0, -1.0, 0.0), true),
+            (Point3::new(9.0, -5.0, 6.0), Vector3::new(0.0, 1.0, 0.0), true),
+            (Point3::new(8.0, 2.0, 12.0), Vector3::new(0.0, 0.0, -1.0), true),
+            (Point3::new(6.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), true),
+            (Point3::new(8.0, 1.0, 3.5), Vector3::new(0.0, 0.0, 1.0), true),
+            (Point3::new(9.0, -1.0, -8.0), Vector3::new(2.0, 4.0, 6.0), false),
+            (Point3::new(8.0, 3.0, -4.0), Vector3::new(6.0, 2.0, 4.0), false),
+            (Point3::new(9.0, -1.0, -2.0), Vector3::new(4.0, 6.0, 2.0), false),
+            (Point3::new(4.0, 0.0, 9.0), Vector3::new(0.0, 0.0, -1.0), false),
+            (Point3::new(8.0, 6.0, -1.0), Vector3::new(0.0, -1.0, 0.0), false),
+            (Point3::new(12.0, 5.0, 4.0), Vector3::new(-1.0, 0.0, 0.0), false),
+        ];
+        for (origin, direction, hit) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(b.intersects(&r), hit, "{origin:?} {direction:?}");
+        }
+    }
+}
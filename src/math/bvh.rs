@@ -0,0 +1,322 @@
+use nalgebra::Point3;
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+
+/// Number of buckets centroids are sorted into when looking for the cheapest split
+const BINS: usize = 12;
+
+/// Leaves only hold more than this when their objects can't be told apart by their centroids.
+/// Below it, a leaf is made whenever no split looks worth it
+const MAX_LEAF_SIZE: usize = 8;
+
+/// A bounding volume hierarchy over a list of boxes, built with the surface area heuristic.
+///
+/// It only knows about the boxes, by their index in the slice it was built from, so it can
+/// sit next to any list of objects. A ray query visits every object whose box the ray might
+/// go through, and skips whole subtrees whose box it misses.
+///
+/// Unbounded objects, like planes, can't be put in a box. They are kept aside and visited
+/// by every query.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Object indices, reordered so every leaf covers a contiguous run
+    order: Vec<usize>,
+    unbounded: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Leaf { start: usize, count: usize },
+    /// The left child is always the very next node, only the right one needs an index
+    Interior { right: usize },
+}
+
+impl Bvh {
+    /// Build the hierarchy over `bounds`, one box per object
+    pub fn build(bounds: &[Aabb]) -> Self {
+        let mut bvh = Self::default();
+        let mut order = Vec::with_capacity(bounds.len());
+        for (i, b) in bounds.iter().enumerate() {
+            if b.is_empty() {
+                // Nothing to hit, e.g. an empty group
+                continue;
+            }
+            if b.is_finite() {
+                order.push(i);
+            } else {
+                bvh.unbounded.push(i);
+            }
+        }
+
+        if !order.is_empty() {
+            let centroids: Vec<Point3<f32>> = bounds.iter().map(Aabb::centroid).collect();
+            let count = order.len();
+            bvh.order = order;
+            bvh.build_node(bounds, &centroids, 0, count);
+        }
+        bvh
+    }
+
+    /// The box around every bounded object
+    pub fn bounds(&self) -> Aabb {
+        self.nodes.first().map_or_else(Aabb::empty, |root| root.bounds)
+    }
+
+    /// Call `visit` with the index of every object the ray might hit
+    pub fn query(&self, ray: &Ray, mut visit: impl FnMut(usize)) {
+        for &i in &self.unbounded {
+            visit(i);
+        }
+        if self.nodes.is_empty() {
+            return;
+        }
+
+        let mut stack = vec![0];
+        while let Some(i) = stack.pop() {
+            let node = &self.nodes[i];
+            if !node.bounds.intersects(ray) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    for &object in &self.order[start..start + count] {
+                        visit(object);
+                    }
+                }
+                NodeKind::Interior { right } => {
+                    stack.push(right);
+                    stack.push(i + 1);
+                }
+            }
+        }
+    }
+
+    /// Build the subtree over `order[start..end]`, returning the index of its root node
+    fn build_node(&mut self, bounds: &[Aabb], centroids: &[Point3<f32>], start: usize, end: usize) -> usize {
+        let items = &self.order[start..end];
+        let node_bounds = items.iter().fold(Aabb::empty(), |b, &i| b.merge(&bounds[i]));
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds: node_bounds,
+            kind: NodeKind::Leaf { start, count: end - start },
+        });
+
+        let Some(split) = self.find_split(bounds, centroids, start, end, &node_bounds) else {
+            return index;
+        };
+
+        // Move everything left of the split to the front of the range
+        let items = &mut self.order[start..end];
+        let mut mid = 0;
+        for j in 0..items.len() {
+            if split.goes_left(&centroids[items[j]]) {
+                items.swap(mid, j);
+                mid += 1;
+            }
+        }
+
+        self.build_node(bounds, centroids, start, start + mid);
+        let right = self.build_node(bounds, centroids, start + mid, end);
+        self.nodes[index].kind = NodeKind::Interior { right };
+        index
+    }
+
+    /// The cheapest way to split `order[start..end]` in two, if that's cheaper than a leaf.
+    ///
+    /// The surface area heuristic: the chance of a ray going through a child box is about
+    /// its area over the parent's, so a split costs one more box test plus each side's object
+    /// count weighted by that chance. A leaf costs testing every object in it.
+    fn find_split(
+        &self,
+        bounds: &[Aabb],
+        centroids: &[Point3<f32>],
+        start: usize,
+        end: usize,
+        node_bounds: &Aabb,
+    ) -> Option<Split> {
+        let items = &self.order[start..end];
+        if items.len() <= 1 {
+            return None;
+        }
+
+        let mut centroid_bounds = Aabb::empty();
+        for &i in items {
+            centroid_bounds.add_point(&centroids[i]);
+        }
+
+        let parent_area = node_bounds.surface_area();
+        let mut best: Option<(f32, Split)> = None;
+        let axes = centroid_bounds.min.iter().zip(centroid_bounds.max.iter()).enumerate();
+        for (axis, (&min, &max)) in axes {
+            let extent = max - min;
+            if extent <= 0.0 {
+                // Every centroid is in the same spot along this axis, nothing to split
+                continue;
+            }
+
+            let mut bins = [(Aabb::empty(), 0usize); BINS];
+            for &i in items {
+                let bin = bin_of(centroids[i][axis], min, extent);
+                bins[bin].0.add_box(&bounds[i]);
+                bins[bin].1 += 1;
+            }
+
+            // Sweep from the right once so each split only needs a sweep from the left
+            let mut right_costs = [0.0; BINS];
+            let mut right = (Aabb::empty(), 0);
+            for b in (1..BINS).rev() {
+                right.0.add_box(&bins[b].0);
+                right.1 += bins[b].1;
+                right_costs[b] = right.0.surface_area() * right.1 as f32;
+            }
+
+            let mut left = (Aabb::empty(), 0);
+            for b in 1..BINS {
+                left.0.add_box(&bins[b - 1].0);
+                left.1 += bins[b - 1].1;
+                if left.1 == 0 || left.1 == items.len() {
+                    continue;
+                }
+                let cost = 1.0 + (left.0.surface_area() * left.1 as f32 + right_costs[b]) / parent_area;
+                if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                    best = Some((cost, Split { axis, min, extent, bin: b }));
+                }
+            }
+        }
+
+        let (cost, split) = best?;
+        if cost >= items.len() as f32 && items.len() <= MAX_LEAF_SIZE {
+            return None;
+        }
+        Some(split)
+    }
+}
+
+/// A plane between two bins along one axis
+#[derive(Debug, Copy, Clone)]
+struct Split {
+    axis: usize,
+    min: f32,
+    extent: f32,
+    bin: usize,
+}
+
+impl Split {
+    fn goes_left(&self, centroid: &Point3<f32>) -> bool {
+        bin_of(centroid[self.axis], self.min, self.extent) < self.bin
+    }
+}
+
+fn bin_of(x: f32, min: f32, extent: f32) -> usize {
+    (((x - min) / extent * BINS as f32) as usize).min(BINS - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::Vector3;
+
+    fn unit_box_at(x: f32, y: f32, z: f32) -> Aabb {
+        Aabb::new(Point3::new(x - 0.5, y - 0.5, z - 0.5), Point3::new(x + 0.5, y + 0.5, z + 0.5))
+    }
+
+    fn query(bvh: &Bvh, ray: &Ray) -> Vec<usize> {
+        let mut hits = Vec::new();
+        bvh.query(ray, |i| hits.push(i));
+        hits.sort();
+        hits
+    }
+
+    #[test]
+    fn empty() {
+        let bvh = Bvh::build(&[]);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(query(&bvh, &r).is_empty());
+        assert!(bvh.bounds().is_empty());
+    }
+
+    #[test]
+    fn bounds_cover_everything() {
+        let boxes = [unit_box_at(-3.0, 0.0, 0.0), unit_box_at(4.0, 2.0, 1.0)];
+        let bvh = Bvh::build(&boxes);
+        assert_eq!(bvh.bounds(), Aabb::new(Point3::new(-3.5, -0.5, -0.5), Point3::new(4.5, 2.5, 1.5)));
+    }
+
+    #[test]
+    fn query_skips_missed_boxes() {
+        // A 10x10 grid of boxes in the xy plane, the ray goes through exactly one column
+        let boxes: Vec<Aabb> = (0..100)
+            .map(|i| unit_box_at((i % 10) as f32 * 2.0, (i / 10) as f32 * 2.0, 0.0))
+            .collect();
+        let bvh = Bvh::build(&boxes);
+
+        let r = Ray::new(Point3::new(6.0, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let hits = query(&bvh, &r);
+        assert_eq!(hits, (0..10).map(|row| row * 10 + 3).collect::<Vec<_>>());
+
+        let r = Ray::new(Point3::new(7.0, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert!(query(&bvh, &r).is_empty());
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let boxes: Vec<Aabb> = (0..200)
+            .map(|i| {
+                let f = i as f32;
+                unit_box_at((f * 7.3) % 20.0 - 10.0, (f * 3.1) % 20.0 - 10.0, (f * 5.7) % 20.0 - 10.0)
+            })
+            .collect();
+        let bvh = Bvh::build(&boxes);
+
+        for k in 0..50 {
+            let f = k as f32;
+            let direction = Vector3::new(f.sin(), (f * 0.7).cos(), 1.0).normalize();
+            let r = Ray::new(Point3::new(f % 5.0, -(f % 3.0), -20.0), direction);
+            let expected: Vec<usize> = (0..boxes.len()).filter(|&i| boxes[i].intersects(&r)).collect();
+            assert_eq!(query(&bvh, &r), expected);
+        }
+    }
+
+    #[test]
+    fn splits_into_a_tree() {
+        let boxes: Vec<Aabb> = (0..64).map(|i| unit_box_at(i as f32 * 3.0, 0.0, 0.0)).collect();
+        let bvh = Bvh::build(&boxes);
+        assert!(bvh.nodes.len() > 1);
+        for node in &bvh.nodes {
+            if let NodeKind::Leaf { count, .. } = node.kind {
+                assert!(count <= MAX_LEAF_SIZE);
+            }
+        }
+    }
+
+    #[test]
+    fn unbounded_always_visited() {
+        let plane = Aabb::new(
+            Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+            Point3::new(f32::INFINITY, 0.0, f32::INFINITY),
+        );
+        let boxes = [unit_box_at(0.0, 5.0, 0.0), plane, Aabb::empty()];
+        let bvh = Bvh::build(&boxes);
+
+        let r = Ray::new(Point3::new(10.0, 10.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(query(&bvh, &r), vec![1]);
+        let r = Ray::new(Point3::new(0.0, 5.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(query(&bvh, &r), vec![0, 1]);
+    }
+
+    #[test]
+    fn identical_boxes() {
+        // No split can separate them, they all end up in leaves without recursing forever
+        let boxes = vec![unit_box_at(1.0, 1.0, 1.0); 20];
+        let bvh = Bvh::build(&boxes);
+        let r = Ray::new(Point3::new(1.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(query(&bvh, &r).len(), 20);
+    }
+}
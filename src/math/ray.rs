@@ -0,0 +1,99 @@
+use nalgebra::{Point3, Vector3};
+use crate::math::affine::ToAffine3;
+
+/// A ray is a half-line: it starts at `origin` and travels along `direction`.
+///
+/// The direction is deliberately not normalized. Transforming a ray into object space
+/// scales the direction along with everything else, and intersection `t` values stay
+/// comparable between spaces because of it.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>,
+}
+
+impl Ray {
+    pub fn new(origin: Point3<f32>, direction: Vector3<f32>) -> Self {
+        Self { origin, direction }
+    }
+
+    /// The point at distance `t` along the ray
+    pub fn position(&self, t: f32) -> Point3<f32> {
+        self.origin + self.direction * t
+    }
+
+    /// Apply a transformation to the ray, returning a new ray.
+    ///
+    /// Accepts anything that can be viewed as an `Affine3`: an `Affine3` built with
+    /// `Affine3Ext`, a `Translation3`, a `Rotation3`, a `Scale3`, ...
+    /// The origin is moved as a point and the direction as a vector, so translations
+    /// leave the direction untouched.
+    pub fn transform<M: ToAffine3<f32>>(&self, m: &M) -> Self {
+        let m = m.to_affine3();
+        Self {
+            origin: m * self.origin,
+            direction: m * self.direction,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::affine::Affine3Ext;
+    use approx::assert_relative_eq;
+    use nalgebra::{Affine3, Rotation3, Scale3, Translation3};
+
+    #[test]
+    fn new() {
+        let origin = Point3::new(1.0, 2.0, 3.0);
+        let direction = Vector3::new(4.0, 5.0, 6.0);
+        let r = Ray::new(origin, direction);
+        assert_relative_eq!(r.origin, origin);
+        assert_relative_eq!(r.direction, direction);
+    }
+
+    #[test]
+    fn position() {
+        let r = Ray::new(Point3::new(2.0, 3.0, 4.0), Vector3::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(r.position(0.0), Point3::new(2.0, 3.0, 4.0));
+        assert_relative_eq!(r.position(1.0), Point3::new(3.0, 3.0, 4.0));
+        assert_relative_eq!(r.position(-1.0), Point3::new(1.0, 3.0, 4.0));
+        assert_relative_eq!(r.position(2.5), Point3::new(4.5, 3.0, 4.0));
+    }
+
+    #[test]
+    fn translate() {
+        let r = Ray::new(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 1.0, 0.0));
+        let r2 = r.transform(&Translation3::new(3.0, 4.0, 5.0));
+        assert_relative_eq!(r2.origin, Point3::new(4.0, 6.0, 8.0));
+        assert_relative_eq!(r2.direction, Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn scale() {
+        let r = Ray::new(Point3::new(1.0, 2.0, 3.0), Vector3::new(0.0, 1.0, 0.0));
+        let r2 = r.transform(&Affine3::from_scale(Scale3::new(2.0, 3.0, 4.0)));
+        assert_relative_eq!(r2.origin, Point3::new(2.0, 6.0, 12.0));
+        assert_relative_eq!(r2.direction, Vector3::new(0.0, 3.0, 0.0));
+    }
+
+    #[test]
+    fn rotate() {
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let rot = Rotation3::from_axis_angle(&Vector3::x_axis(), std::f32::consts::FRAC_PI_2);
+        let r2 = r.transform(&rot);
+        assert_relative_eq!(r2.origin, Point3::new(0.0, 0.0, 1.0), epsilon = 1e-6);
+        assert_relative_eq!(r2.direction, Vector3::new(0.0, 0.0, 1.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn chained() {
+        let r = Ray::new(Point3::new(1.0, 0.0, 1.0), Vector3::new(0.0, 0.0, 1.0));
+        let t = Translation3::new(10.0, 5.0, 7.0);
+        let sc = Affine3::from_scale(Scale3::new(5.0, 5.0, 5.0));
+        let r2 = r.transform(&(t * sc));
+        assert_relative_eq!(r2.origin, Point3::new(15.0, 5.0, 12.0));
+        assert_relative_eq!(r2.direction, Vector3::new(0.0, 0.0, 5.0));
+    }
+}
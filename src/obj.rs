@@ -0,0 +1,468 @@
+use std::fmt;
+use std::str::FromStr;
+use nalgebra::{Point2, Point3, Vector3};
+use crate::shape::group::Group;
+use crate::shape::smooth_triangle::SmoothTriangle;
+use crate::shape::triangle::Triangle;
+use crate::shape::Shape;
+
+/// A mesh read from a Wavefront OBJ file.
+///
+/// Understands vertices (`v`), vertex normals (`vn`), texture coordinates (`vt`), faces (`f`)
+/// and groups (`g` and `o`). Faces with more than three vertices are split into a fan of
+/// triangles around their first vertex. Anything else, like materials, is skipped and counted
+/// in `ignored_lines`; blank lines and `#` comments are skipped without counting.
+///
+/// Indices in the file start at 1, or count back from the end when negative. Everything here
+/// has already been resolved to ordinary 0-based indices.
+#[derive(Debug, Clone, Default)]
+pub struct ObjFile {
+    vertices: Vec<Point3<f32>>,
+    normals: Vec<Vector3<f32>>,
+    texture_coords: Vec<Point2<f32>>,
+    /// The unnamed default group first, then named groups in the order they first appear
+    groups: Vec<ObjGroup>,
+    ignored_lines: usize,
+}
+
+/// The faces listed under one `g` or `o` record
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ObjGroup {
+    /// `None` for faces that come before any group record
+    pub name: Option<String>,
+    pub triangles: Vec<[FaceVertex; 3]>,
+}
+
+/// One corner of a face: indices into the vertices, texture coordinates and normals
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FaceVertex {
+    pub vertex: usize,
+    pub texture: Option<usize>,
+    pub normal: Option<usize>,
+}
+
+impl ObjFile {
+    pub fn parse(source: &str) -> Result<Self, ObjError> {
+        let mut obj = Self {
+            groups: vec![ObjGroup::default()],
+            ..Self::default()
+        };
+        let mut current = 0;
+
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i + 1;
+            let error = |kind| ObjError { line: line_number, kind };
+
+            // A comment runs from `#` to the end of the line, whether or not a record comes first
+            let line = line.split_once('#').map_or(line, |(before, _)| before);
+            let mut fields = line.split_whitespace();
+            let Some(record) = fields.next() else {
+                continue;
+            };
+            let values: Vec<&str> = fields.collect();
+
+            match record {
+                "v" => {
+                    let [x, y, z] = parse_numbers(record, &values).map_err(error)?;
+                    obj.vertices.push(Point3::new(x, y, z));
+                }
+                "vn" => {
+                    let [x, y, z] = parse_numbers(record, &values).map_err(error)?;
+                    obj.normals.push(Vector3::new(x, y, z));
+                }
+                "vt" => {
+                    // A third, depth, coordinate is allowed but not used
+                    let [u, v] = parse_numbers(record, &values).map_err(error)?;
+                    obj.texture_coords.push(Point2::new(u, v));
+                }
+                "f" => {
+                    if values.len() < 3 {
+                        return Err(error(ObjErrorKind::TooFewVertices(values.len())));
+                    }
+                    let corners = values
+                        .iter()
+                        .map(|value| obj.face_vertex(value))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(error)?;
+                    // Fan out from the first corner
+                    let triangles = &mut obj.groups[current].triangles;
+                    for pair in corners[1..].windows(2) {
+                        triangles.push([corners[0], pair[0], pair[1]]);
+                    }
+                }
+                "g" | "o" => {
+                    let name = values.join(" ");
+                    current = if name.is_empty() {
+                        0
+                    } else {
+                        obj.group_index(&name).unwrap_or_else(|| {
+                            obj.groups.push(ObjGroup {
+                                name: Some(name),
+                                triangles: Vec::new(),
+                            });
+                            obj.groups.len() - 1
+                        })
+                    };
+                }
+                _ => obj.ignored_lines += 1,
+            }
+        }
+
+        Ok(obj)
+    }
+
+    pub fn vertices(&self) -> &[Point3<f32>] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector3<f32>] {
+        &self.normals
+    }
+
+    pub fn texture_coords(&self) -> &[Point2<f32>] {
+        &self.texture_coords
+    }
+
+    /// Every group, starting with the default one, even when it is empty
+    pub fn groups(&self) -> &[ObjGroup] {
+        &self.groups
+    }
+
+    /// The faces that came before any group record
+    pub fn default_group(&self) -> &ObjGroup {
+        &self.groups[0]
+    }
+
+    pub fn group(&self, name: &str) -> Option<&ObjGroup> {
+        self.group_index(name).map(|i| &self.groups[i])
+    }
+
+    /// How many lines were not understood and skipped
+    pub fn ignored_lines(&self) -> usize {
+        self.ignored_lines
+    }
+
+    /// A shape for one triangle of the mesh.
+    ///
+    /// Triangles with a normal at every corner are smooth, the rest are flat.
+    pub fn triangle(&self, corners: &[FaceVertex; 3]) -> Box<dyn Shape> {
+        let [p1, p2, p3] = corners.map(|c| self.vertices[c.vertex]);
+        match corners.map(|c| c.normal) {
+            [Some(n1), Some(n2), Some(n3)] => Box::new(SmoothTriangle::new(
+                p1,
+                p2,
+                p3,
+                self.normals[n1],
+                self.normals[n2],
+                self.normals[n3],
+            )),
+            _ => Box::new(Triangle::new(p1, p2, p3)),
+        }
+    }
+
+    /// The whole mesh as one group, ready to be placed in a world.
+    ///
+    /// The default group's triangles are its direct children, every named group becomes
+    /// a child group of its own.
+    pub fn to_group(&self) -> Group {
+        let mut group = Group::new();
+        for triangle in &self.default_group().triangles {
+            group.add_boxed_child(self.triangle(triangle));
+        }
+        for named in &self.groups[1..] {
+            if named.triangles.is_empty() {
+                continue;
+            }
+            let mut child = Group::new();
+            for triangle in &named.triangles {
+                child.add_boxed_child(self.triangle(triangle));
+            }
+            group.add_child(child);
+        }
+        group
+    }
+
+    fn group_index(&self, name: &str) -> Option<usize> {
+        self.groups.iter().position(|g| g.name.as_deref() == Some(name))
+    }
+
+    /// Parse one `v`, `v/vt`, `v//vn` or `v/vt/vn` corner of a face
+    fn face_vertex(&self, value: &str) -> Result<FaceVertex, ObjErrorKind> {
+        let mut parts = value.split('/');
+        let vertex = parts.next().unwrap_or_default();
+        let texture = parts.next().filter(|s| !s.is_empty());
+        let normal = parts.next().filter(|s| !s.is_empty());
+        if vertex.is_empty() || parts.next().is_some() {
+            return Err(ObjErrorKind::InvalidFaceVertex(value.to_string()));
+        }
+
+        Ok(FaceVertex {
+            vertex: resolve_index(vertex, self.vertices.len())?,
+            texture: texture.map(|i| resolve_index(i, self.texture_coords.len())).transpose()?,
+            normal: normal.map(|i| resolve_index(i, self.normals.len())).transpose()?,
+        })
+    }
+}
+
+/// The first `N` values of a record as numbers, any more are left alone
+fn parse_numbers<const N: usize>(record: &str, values: &[&str]) -> Result<[f32; N], ObjErrorKind> {
+    if values.len() < N {
+        return Err(ObjErrorKind::MissingValues {
+            record: record.to_string(),
+            expected: N,
+            found: values.len(),
+        });
+    }
+
+    let mut numbers = [0.0; N];
+    for (number, value) in numbers.iter_mut().zip(values) {
+        *number = parse(value)?;
+    }
+    Ok(numbers)
+}
+
+fn parse<T: FromStr>(value: &str) -> Result<T, ObjErrorKind> {
+    value.parse().map_err(|_| ObjErrorKind::InvalidNumber(value.to_string()))
+}
+
+/// Turn a 1-based, or negative and relative to the end, OBJ index into a 0-based one
+fn resolve_index(value: &str, len: usize) -> Result<usize, ObjErrorKind> {
+    let index: i64 = parse(value)?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        len as i64 + index
+    };
+
+    if index == 0 || resolved < 0 || resolved >= len as i64 {
+        return Err(ObjErrorKind::IndexOutOfRange { index, len });
+    }
+    Ok(resolved as usize)
+}
+
+/// A line of an OBJ file that could not be read
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjError {
+    /// Counted from 1
+    pub line: usize,
+    pub kind: ObjErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ObjErrorKind {
+    /// A value that should be a number but isn't
+    InvalidNumber(String),
+    /// A record with fewer values than it needs, like a vertex with only two coordinates
+    MissingValues {
+        record: String,
+        expected: usize,
+        found: usize,
+    },
+    /// A face corner that isn't one of the `v`, `v/vt`, `v//vn` or `v/vt/vn` forms
+    InvalidFaceVertex(String),
+    /// A face index that is zero or points past the elements read so far
+    IndexOutOfRange { index: i64, len: usize },
+    /// A face with fewer than three corners
+    TooFewVertices(usize),
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.kind)
+    }
+}
+
+impl fmt::Display for ObjErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidNumber(value) => write!(f, "invalid number `{value}`"),
+            Self::MissingValues { record, expected, found } => {
+                write!(f, "`{record}` needs {expected} values, found {found}")
+            }
+            Self::InvalidFaceVertex(value) => write!(f, "invalid face vertex `{value}`"),
+            Self::IndexOutOfRange { index, len } => {
+                write!(f, "index {index} is out of range, there are {len} so far")
+            }
+            Self::TooFewVertices(count) => write!(f, "a face needs at least 3 vertices, found {count}"),
+        }
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use crate::math::ray::Ray;
+
+    fn corner(vertex: usize) -> FaceVertex {
+        FaceVertex {
+            vertex,
+            texture: None,
+            normal: None,
+        }
+    }
+
+    #[test]
+    fn ignores_unrecognized_lines() {
+        let gibberish = "There was a young lady named Bright\n\
+                         who traveled much faster than light.\n\
+                         \n\
+                         # She set out one day\n\
+                         in a relative way,\n\
+                         and came back the previous night.";
+        let obj = ObjFile::parse(gibberish).unwrap();
+        assert_eq!(obj.ignored_lines(), 4);
+        assert!(obj.vertices().is_empty());
+    }
+
+    #[test]
+    fn vertices() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0").unwrap();
+        assert_eq!(obj.vertices().len(), 4);
+        assert_relative_eq!(obj.vertices()[0], Point3::new(-1.0, 1.0, 0.0));
+        assert_relative_eq!(obj.vertices()[1], Point3::new(-1.0, 0.5, 0.0));
+        assert_relative_eq!(obj.vertices()[2], Point3::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(obj.vertices()[3], Point3::new(1.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn trailing_comments() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1 0 0 # corner\nv 1 0 0 # tip\nf 1 2 3 # quad half").unwrap();
+        assert_eq!(obj.ignored_lines(), 0);
+        assert_relative_eq!(obj.vertices()[2], Point3::new(1.0, 0.0, 0.0));
+        assert_eq!(obj.default_group().triangles, vec![[corner(0), corner(1), corner(2)]]);
+    }
+
+    #[test]
+    fn triangle_faces() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4").unwrap();
+        let triangles = &obj.default_group().triangles;
+        assert_eq!(triangles, &vec![[corner(0), corner(1), corner(2)], [corner(0), corner(2), corner(3)]]);
+    }
+
+    #[test]
+    fn polygon_fan() {
+        let obj = ObjFile::parse("v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5").unwrap();
+        let triangles = &obj.default_group().triangles;
+        assert_eq!(
+            triangles,
+            &vec![
+                [corner(0), corner(1), corner(2)],
+                [corner(0), corner(2), corner(3)],
+                [corner(0), corner(3), corner(4)],
+            ]
+        );
+    }
+
+    #[test]
+    fn named_groups() {
+        let source = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                      g FirstGroup\nf 1 2 3\n\
+                      o SecondGroup\nf 1 3 4\n\
+                      g FirstGroup\nf 2 3 4";
+        let obj = ObjFile::parse(source).unwrap();
+        assert!(obj.default_group().triangles.is_empty());
+        assert_eq!(obj.group("FirstGroup").unwrap().triangles.len(), 2);
+        assert_eq!(obj.group("SecondGroup").unwrap().triangles, vec![[corner(0), corner(2), corner(3)]]);
+        assert!(obj.group("ThirdGroup").is_none());
+        assert_eq!(obj.groups().len(), 3);
+    }
+
+    #[test]
+    fn vertex_normals() {
+        let obj = ObjFile::parse("vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3").unwrap();
+        assert_relative_eq!(obj.normals()[0], Vector3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(obj.normals()[1], Vector3::new(0.707, 0.0, -0.707));
+        assert_relative_eq!(obj.normals()[2], Vector3::new(1.0, 2.0, 3.0));
+    }
+
+    #[test]
+    fn texture_coords() {
+        let obj = ObjFile::parse("vt 0.5 0.25\nvt 1 0 0").unwrap();
+        assert_relative_eq!(obj.texture_coords()[0], Point2::new(0.5, 0.25));
+        assert_relative_eq!(obj.texture_coords()[1], Point2::new(1.0, 0.0));
+    }
+
+    #[test]
+    fn face_vertex_forms() {
+        let source = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                      vt 0 0\nvt 1 0\nvt 0 1\n\
+                      vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+                      f 1//3 2//1 3//2\n\
+                      f 1/1/3 2/2/1 3/3/2\n\
+                      f 1/1 2/2 3/3\n\
+                      f -3/-3/-1 -2/-2/-3 -1/-1/-2";
+        let obj = ObjFile::parse(source).unwrap();
+        let triangles = &obj.default_group().triangles;
+        let with = |vertex, texture, normal| FaceVertex { vertex, texture, normal };
+
+        assert_eq!(triangles[0], [with(0, None, Some(2)), with(1, None, Some(0)), with(2, None, Some(1))]);
+        let full = [with(0, Some(0), Some(2)), with(1, Some(1), Some(0)), with(2, Some(2), Some(1))];
+        assert_eq!(triangles[1], full);
+        assert_eq!(triangles[2], [with(0, Some(0), None), with(1, Some(1), None), with(2, Some(2), None)]);
+        assert_eq!(triangles[3], full);
+    }
+
+    #[test]
+    fn to_group() {
+        let source = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                      f 1 2 3\n\
+                      g FirstGroup\nf 1 2 3\n\
+                      g SecondGroup\nf 1 3 4\n\
+                      g Empty";
+        let g = ObjFile::parse(source).unwrap().to_group();
+        // The default group's triangle and the two non-empty named groups
+        assert_eq!(g.children().len(), 3);
+
+        let r = Ray::new(Point3::new(0.5, 0.8, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 1);
+        let r = Ray::new(Point3::new(-0.5, 0.2, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    #[test]
+    fn smooth_triangles_from_normals() {
+        let source = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                      vn -1 0 0\nvn 1 0 0\nvn 0 1 0\n\
+                      f 1//3 2//1 3//2";
+        let g = ObjFile::parse(source).unwrap().to_group();
+        let r = Ray::new(Point3::new(-0.2, 0.3, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        let point = r.position(xs[0].t);
+        // A flat triangle would face straight down -z
+        let n = xs[0].object.normal_at_hit(&point, &xs[0]);
+        assert_relative_eq!(n, Vector3::new(-0.5547, 0.83205, 0.0), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("v 1 2", ObjError {
+                line: 1,
+                kind: ObjErrorKind::MissingValues { record: "v".to_string(), expected: 3, found: 2 },
+            }),
+            ("v 1 2 3\n\nvn 1 x 3", ObjError { line: 3, kind: ObjErrorKind::InvalidNumber("x".to_string()) }),
+            ("v 1 2 3\nv 1 2 3\nf 1 2", ObjError { line: 3, kind: ObjErrorKind::TooFewVertices(2) }),
+            ("v 1 2 3\nf 1 1 2", ObjError { line: 2, kind: ObjErrorKind::IndexOutOfRange { index: 2, len: 1 } }),
+            ("v 1 2 3\nf 1 1 0", ObjError { line: 2, kind: ObjErrorKind::IndexOutOfRange { index: 0, len: 1 } }),
+            ("v 1 2 3\nf 1 1 -2", ObjError { line: 2, kind: ObjErrorKind::IndexOutOfRange { index: -2, len: 1 } }),
+            ("v 1 2 3\nf 1/1 1 1", ObjError { line: 2, kind: ObjErrorKind::IndexOutOfRange { index: 1, len: 0 } }),
+            ("v 1 2 3\nf 1 1 1/1/1/1", ObjError {
+                line: 2,
+                kind: ObjErrorKind::InvalidFaceVertex("1/1/1/1".to_string()),
+            }),
+            ("v 1 2 3\nf 1 1 /1", ObjError { line: 2, kind: ObjErrorKind::InvalidFaceVertex("/1".to_string()) }),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(ObjFile::parse(source).unwrap_err(), expected, "{source}");
+        }
+    }
+
+    #[test]
+    fn error_message() {
+        let err = ObjFile::parse("v 1 2 3\nf 1 1 7").unwrap_err();
+        assert_eq!(err.to_string(), "line 2: index 7 is out of range, there are 1 so far");
+    }
+}
@@ -0,0 +1,217 @@
+pub mod blend;
+pub mod checker;
+pub mod gradient;
+pub mod perturb;
+pub mod ring;
+pub mod solid;
+pub mod stripe;
+
+use std::fmt::Debug;
+use std::sync::Arc;
+use nalgebra::{Affine3, Point3};
+use crate::canvas::color::Color;
+use crate::math::affine::ToAffine3;
+use crate::shape::Shape;
+use solid::Solid;
+
+/// Where a pattern sits relative to whatever owns it, an object or a parent pattern.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternBase {
+    transform: Affine3<f32>,
+    inverse: Affine3<f32>,
+}
+
+impl PatternBase {
+    pub fn new() -> Self {
+        Self {
+            transform: Affine3::identity(),
+            inverse: Affine3::identity(),
+        }
+    }
+}
+
+impl Default for PatternBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A procedural, 3D surface color.
+///
+/// Like `Shape`, implementors only describe the pattern in their own pattern space
+/// through `local_pattern_at`. The provided methods take care of the object's transform
+/// and then the pattern's own transform on top of it.
+pub trait Pattern: Debug + Send + Sync {
+    fn base(&self) -> &PatternBase;
+
+    fn base_mut(&mut self) -> &mut PatternBase;
+
+    /// The color at a point already in pattern space
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color;
+
+    /// The pattern to owner transformation
+    fn transform(&self) -> &Affine3<f32> {
+        &self.base().transform
+    }
+
+    fn inverse(&self) -> &Affine3<f32> {
+        &self.base().inverse
+    }
+
+    /// Place the pattern in its owner's space.
+    ///
+    /// # Panics
+    ///
+    /// If the transform can't be inverted, like a scale of zero along some axis.
+    fn set_transform<M: ToAffine3<f32>>(&mut self, transform: M)
+    where
+        Self: Sized,
+    {
+        let transform = transform.to_affine3();
+        self.base_mut().inverse = transform
+            .try_inverse()
+            .expect("a pattern's transform must be invertible, it can't scale any axis to zero");
+        self.base_mut().transform = transform;
+    }
+
+    /// The color at a point in the owner's space: object space for a material's pattern,
+    /// the parent's pattern space for a nested pattern.
+    fn pattern_at(&self, point: &Point3<f32>) -> Color {
+        self.local_pattern_at(&(self.inverse() * point))
+    }
+
+    /// The color of `object` at a world space point
+    fn pattern_at_shape(&self, object: &dyn Shape, point: &Point3<f32>) -> Color {
+        self.pattern_at(&object.world_to_object(point))
+    }
+}
+
+/// Anything that can stand in for a pattern: a pattern, or a plain `Color` as a solid one.
+///
+/// Lets patterns nest without boxing by hand, e.g. `Stripe::new(white, Ring::new(red, blue))`.
+pub trait IntoPattern {
+    fn into_pattern(self) -> Arc<dyn Pattern>;
+}
+
+impl<P: Pattern + 'static> IntoPattern for P {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        Arc::new(self)
+    }
+}
+
+impl IntoPattern for Color {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        Arc::new(Solid::new(self))
+    }
+}
+
+impl IntoPattern for Arc<dyn Pattern> {
+    fn into_pattern(self) -> Arc<dyn Pattern> {
+        self
+    }
+}
+
+/// Alternate between two patterns every whole unit of `x`, used by stripes, rings and checkers.
+fn is_even(x: f32) -> bool {
+    (x.floor() as i64).rem_euclid(2) == 0
+}
+
+/// A pattern that paints the point it was given, to see which space it ended up in
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct TestPattern {
+    base: PatternBase,
+}
+
+#[cfg(test)]
+impl Pattern for TestPattern {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        Color::new(point.x, point.y, point.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{Scale3, Translation3};
+    use crate::math::affine::Affine3Ext;
+    use crate::shape::sphere::Sphere;
+
+    #[test]
+    fn default_transform() {
+        let p = TestPattern::default();
+        assert_relative_eq!(p.transform().matrix(), Affine3::identity().matrix());
+    }
+
+    #[test]
+    fn assign_transform() {
+        let mut p = TestPattern::default();
+        p.set_transform(Translation3::new(1.0, 2.0, 3.0));
+        assert_relative_eq!(p.transform().matrix(), &Translation3::new(1.0, 2.0, 3.0).to_homogeneous());
+    }
+
+    #[test]
+    #[should_panic(expected = "must be invertible")]
+    fn singular_transform() {
+        let mut p = TestPattern::default();
+        p.set_transform(Scale3::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn object_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        let p = TestPattern::default();
+        let c = p.pattern_at_shape(&shape, &Point3::new(2.0, 3.0, 4.0));
+        assert_relative_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn pattern_transformation() {
+        let shape = Sphere::new();
+        let mut p = TestPattern::default();
+        p.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        let c = p.pattern_at_shape(&shape, &Point3::new(2.0, 3.0, 4.0));
+        assert_relative_eq!(c, Color::new(1.0, 1.5, 2.0));
+    }
+
+    #[test]
+    fn object_and_pattern_transformation() {
+        let mut shape = Sphere::new();
+        shape.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        let mut p = TestPattern::default();
+        p.set_transform(Translation3::new(0.5, 1.0, 1.5));
+        let c = p.pattern_at_shape(&shape, &Point3::new(2.5, 3.0, 3.5));
+        assert_relative_eq!(c, Color::new(0.75, 0.5, 0.25));
+    }
+
+    #[test]
+    fn nested_transformation() {
+        // The child's transform composes with its parent's, like the object's does with the pattern's
+        let mut child = TestPattern::default();
+        child.set_transform(Translation3::new(1.0, 0.0, 0.0));
+        let mut parent = stripe::Stripe::new(child, Color::new(0.0, 0.0, 0.0));
+        parent.set_transform(Affine3::from_scale(Scale3::new(2.0, 2.0, 2.0)));
+        // parent space x = 0.5 -> even stripe -> child space x = 0.5 - 1
+        let c = parent.pattern_at(&Point3::new(1.0, 4.0, 6.0));
+        assert_relative_eq!(c, Color::new(-0.5, 2.0, 3.0));
+    }
+
+    #[test]
+    fn is_even_negative() {
+        assert!(is_even(0.0));
+        assert!(is_even(0.9));
+        assert!(!is_even(-0.1));
+        assert!(!is_even(1.0));
+        assert!(is_even(-2.0));
+    }
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{IntoPattern, Pattern, PatternBase};
+
+/// Mix two patterns together, e.g. stripes along x over stripes along z for a plaid.
+///
+/// `weight` is the share of `b` in the mix: 0.0 is all `a`, 1.0 is all `b`.
+#[derive(Debug, Clone)]
+pub struct Blend {
+    base: PatternBase,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+    weight: f32,
+}
+
+impl Blend {
+    /// An even mix of both patterns
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self::weighted(a, b, 0.5)
+    }
+
+    pub fn weighted(a: impl IntoPattern, b: impl IntoPattern, weight: f32) -> Self {
+        Self {
+            base: PatternBase::new(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+            weight,
+        }
+    }
+}
+
+impl Pattern for Blend {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        self.a.pattern_at(point) * (1.0 - self.weight) + self.b.pattern_at(point) * self.weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use crate::pattern::stripe::Stripe;
+
+    #[test]
+    fn average() {
+        let p = Blend::new(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), Color::new(0.5, 0.0, 0.5));
+    }
+
+    #[test]
+    fn weighted() {
+        let p = Blend::weighted(Color::new(1.0, 0.0, 0.0), Color::new(0.0, 0.0, 1.0), 0.25);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), Color::new(0.75, 0.0, 0.25));
+    }
+
+    #[test]
+    fn plaid() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let along_x = Stripe::new(white, black);
+        let mut along_z = Stripe::new(white, black);
+        along_z.set_transform(nalgebra::Rotation3::from_axis_angle(
+            &nalgebra::Vector3::y_axis(),
+            std::f32::consts::FRAC_PI_2,
+        ));
+        let p = Blend::new(along_x, along_z);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.5, 0.0, -0.5)), white);
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.5, 0.0, -0.5)), Color::new(0.5, 0.5, 0.5));
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.5, 0.0, 0.5)), black);
+    }
+}
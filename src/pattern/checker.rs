@@ -0,0 +1,85 @@
+use std::sync::Arc;
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{is_even, IntoPattern, Pattern, PatternBase};
+
+/// A 3D checkerboard of unit cubes, alternating along every axis.
+#[derive(Debug, Clone)]
+pub struct Checker {
+    base: PatternBase,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl Checker {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            base: PatternBase::new(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for Checker {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        if is_even(point.x.floor() + point.y.floor() + point.z.floor()) {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn repeats_in_x() {
+        let p = Checker::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.99, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.01, 0.0, 0.0)), black());
+    }
+
+    #[test]
+    fn repeats_in_y() {
+        let p = Checker::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.99, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 1.01, 0.0)), black());
+    }
+
+    #[test]
+    fn repeats_in_z() {
+        let p = Checker::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.99)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 1.01)), black());
+    }
+
+    #[test]
+    fn diagonal_cells_match() {
+        let p = Checker::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.5, 1.5, 0.5)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(-0.5, 0.5, 0.5)), black());
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{IntoPattern, Pattern, PatternBase};
+
+/// A linear blend from `a` to `b` along x, repeating every unit.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    base: PatternBase,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl Gradient {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            base: PatternBase::new(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for Gradient {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        let a = self.a.pattern_at(point);
+        let b = self.b.pattern_at(point);
+        let fraction = point.x - point.x.floor();
+        a + (b - a) * fraction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn interpolates() {
+        let p = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), Color::new(1.0, 1.0, 1.0));
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.5, 0.0, 0.0)), Color::new(0.5, 0.5, 0.5));
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.75, 0.0, 0.0)), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn repeats() {
+        let p = Gradient::new(Color::new(1.0, 1.0, 1.0), Color::new(0.0, 0.0, 0.0));
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.25, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+        assert_relative_eq!(p.pattern_at(&Point3::new(-0.75, 0.0, 0.0)), Color::new(0.75, 0.75, 0.75));
+    }
+}
@@ -0,0 +1,148 @@
+use std::sync::Arc;
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::math::hash;
+use crate::pattern::{IntoPattern, Pattern, PatternBase};
+
+/// Jitter the points fed to another pattern with 3D noise, so its straight edges wobble
+/// like marble veins or wood grain.
+///
+/// `scale` is how far a point may be pushed, in pattern space units.
+#[derive(Debug, Clone)]
+pub struct Perturb {
+    base: PatternBase,
+    pattern: Arc<dyn Pattern>,
+    scale: f32,
+}
+
+impl Perturb {
+    pub fn new(pattern: impl IntoPattern, scale: f32) -> Self {
+        Self {
+            base: PatternBase::new(),
+            pattern: pattern.into_pattern(),
+            scale,
+        }
+    }
+}
+
+impl Pattern for Perturb {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        // Sample the noise field three times, far apart, for three independent offsets
+        let offset = Vector3::new(
+            noise(point),
+            noise(&(point + Vector3::new(31.4, 0.0, 0.0))),
+            noise(&(point + Vector3::new(0.0, 0.0, 27.1))),
+        );
+        self.pattern.pattern_at(&(point + offset * self.scale))
+    }
+}
+
+/// Gradient ("Perlin") noise in roughly -1.0..=1.0, smooth and 0.0 on every lattice point.
+fn noise(point: &Point3<f32>) -> f32 {
+    let cell = point.map(f32::floor);
+    let local = point - cell;
+    let (x, y, z) = (cell.x as i32, cell.y as i32, cell.z as i32);
+
+    // Each corner of the lattice cell contributes the dot of its gradient with the offset to the point
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let g = gradient(hash(x + dx, y + dy, z + dz));
+        g.dot(&(local - Vector3::new(dx as f32, dy as f32, dz as f32)))
+    };
+
+    let u = fade(local.x);
+    let v = fade(local.y);
+    let w = fade(local.z);
+    let lerp = |t: f32, a: f32, b: f32| a + t * (b - a);
+
+    lerp(w,
+        lerp(v,
+            lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+            lerp(u, corner(0, 1, 0), corner(1, 1, 0))),
+        lerp(v,
+            lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+            lerp(u, corner(0, 1, 1), corner(1, 1, 1))))
+}
+
+/// 6t^5 - 15t^4 + 10t^3, eases in and out so the noise has no creases at cell borders
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+/// One of the twelve edge directions of a cube, as in Perlin's improved noise
+fn gradient(hash: u32) -> Vector3<f32> {
+    match hash % 12 {
+        0 => Vector3::new(1.0, 1.0, 0.0),
+        1 => Vector3::new(-1.0, 1.0, 0.0),
+        2 => Vector3::new(1.0, -1.0, 0.0),
+        3 => Vector3::new(-1.0, -1.0, 0.0),
+        4 => Vector3::new(1.0, 0.0, 1.0),
+        5 => Vector3::new(-1.0, 0.0, 1.0),
+        6 => Vector3::new(1.0, 0.0, -1.0),
+        7 => Vector3::new(-1.0, 0.0, -1.0),
+        8 => Vector3::new(0.0, 1.0, 1.0),
+        9 => Vector3::new(0.0, -1.0, 1.0),
+        10 => Vector3::new(0.0, 1.0, -1.0),
+        _ => Vector3::new(0.0, -1.0, -1.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use crate::pattern::stripe::Stripe;
+
+    #[test]
+    fn noise_is_zero_on_lattice() {
+        assert_relative_eq!(noise(&Point3::new(0.0, 0.0, 0.0)), 0.0);
+        assert_relative_eq!(noise(&Point3::new(3.0, -2.0, 7.0)), 0.0);
+    }
+
+    #[test]
+    fn noise_is_bounded_and_repeatable() {
+        for i in 0..1000 {
+            let p = Point3::new(i as f32 * 0.137, i as f32 * -0.071, i as f32 * 0.029);
+            let n = noise(&p);
+            assert!((-1.5..=1.5).contains(&n));
+            assert_relative_eq!(n, noise(&p));
+        }
+    }
+
+    #[test]
+    fn noise_varies() {
+        let a = noise(&Point3::new(0.5, 0.5, 0.5));
+        let b = noise(&Point3::new(1.5, 0.5, 0.5));
+        let c = noise(&Point3::new(0.5, 2.5, -0.5));
+        assert!(a != b || b != c);
+    }
+
+    #[test]
+    fn zero_scale_is_the_inner_pattern() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let p = Perturb::new(Stripe::new(white, black), 0.0);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.5, 0.3, 0.2)), white);
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.5, 0.3, 0.2)), black);
+    }
+
+    #[test]
+    fn wobbles_edges() {
+        // Somewhere along a stripe border the perturbed pattern has to disagree with the plain one
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let plain = Stripe::new(white, black);
+        let perturbed = Perturb::new(Stripe::new(white, black), 0.5);
+        let differs = (0..100)
+            .map(|i| Point3::new(0.95, i as f32 * 0.37, i as f32 * 0.13))
+            .any(|p| plain.pattern_at(&p) != perturbed.pattern_at(&p));
+        assert!(differs);
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::Arc;
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{is_even, IntoPattern, Pattern, PatternBase};
+
+/// Concentric rings around the y axis, one unit wide.
+#[derive(Debug, Clone)]
+pub struct Ring {
+    base: PatternBase,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl Ring {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            base: PatternBase::new(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for Ring {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        let distance = (point.x * point.x + point.z * point.z).sqrt();
+        if is_even(distance) {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn extends_in_x_and_z() {
+        let white = Color::new(1.0, 1.0, 1.0);
+        let black = Color::new(0.0, 0.0, 0.0);
+        let p = Ring::new(white, black);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white);
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.0, 0.0, 0.0)), black);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 1.0)), black);
+        // 0.708 = just slightly more than √2/2
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.708, 0.0, 0.708)), black);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 5.0, 0.5)), white);
+    }
+}
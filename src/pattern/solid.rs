@@ -0,0 +1,47 @@
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{Pattern, PatternBase};
+
+/// The same color everywhere, the leaf of nested patterns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Solid {
+    base: PatternBase,
+    color: Color,
+}
+
+impl Solid {
+    pub fn new(color: Color) -> Self {
+        Self {
+            base: PatternBase::new(),
+            color,
+        }
+    }
+}
+
+impl Pattern for Solid {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, _point: &Point3<f32>) -> Color {
+        self.color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn constant() {
+        let c = Color::new(0.2, 0.4, 0.6);
+        let p = Solid::new(c);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), c);
+        assert_relative_eq!(p.pattern_at(&Point3::new(-7.5, 3.0, 100.0)), c);
+    }
+}
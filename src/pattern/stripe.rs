@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use nalgebra::Point3;
+use crate::canvas::color::Color;
+use crate::pattern::{is_even, IntoPattern, Pattern, PatternBase};
+
+/// Alternating stripes along x, one unit wide, constant in y and z.
+#[derive(Debug, Clone)]
+pub struct Stripe {
+    base: PatternBase,
+    a: Arc<dyn Pattern>,
+    b: Arc<dyn Pattern>,
+}
+
+impl Stripe {
+    pub fn new(a: impl IntoPattern, b: impl IntoPattern) -> Self {
+        Self {
+            base: PatternBase::new(),
+            a: a.into_pattern(),
+            b: b.into_pattern(),
+        }
+    }
+}
+
+impl Pattern for Stripe {
+    fn base(&self) -> &PatternBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut PatternBase {
+        &mut self.base
+    }
+
+    fn local_pattern_at(&self, point: &Point3<f32>) -> Color {
+        if is_even(point.x) {
+            self.a.pattern_at(point)
+        } else {
+            self.b.pattern_at(point)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn white() -> Color {
+        Color::new(1.0, 1.0, 1.0)
+    }
+
+    fn black() -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    #[test]
+    fn constant_in_y() {
+        let p = Stripe::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 1.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 2.0, 0.0)), white());
+    }
+
+    #[test]
+    fn constant_in_z() {
+        let p = Stripe::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 1.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 2.0)), white());
+    }
+
+    #[test]
+    fn alternates_in_x() {
+        let p = Stripe::new(white(), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.0, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.9, 0.0, 0.0)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.0, 0.0, 0.0)), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(-0.1, 0.0, 0.0)), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(-1.0, 0.0, 0.0)), black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(-1.1, 0.0, 0.0)), white());
+    }
+
+    #[test]
+    fn nested() {
+        // Stripes of stripes: the inner ones run along z thanks to their own transform
+        let red = Color::new(1.0, 0.0, 0.0);
+        let mut inner = Stripe::new(red, white());
+        inner.set_transform(nalgebra::Rotation3::from_axis_angle(
+            &nalgebra::Vector3::y_axis(),
+            std::f32::consts::FRAC_PI_2,
+        ));
+        let p = Stripe::new(inner, black());
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.5, 0.0, -0.5)), red);
+        assert_relative_eq!(p.pattern_at(&Point3::new(0.5, 0.0, 0.5)), white());
+        assert_relative_eq!(p.pattern_at(&Point3::new(1.5, 0.0, 0.5)), black());
+    }
+}
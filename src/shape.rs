@@ -0,0 +1,354 @@
+pub mod cone;
+pub mod csg;
+pub mod cube;
+pub mod cylinder;
+pub mod group;
+pub mod plane;
+pub mod smooth_triangle;
+pub mod sphere;
+pub mod triangle;
+
+use std::fmt::Debug;
+use nalgebra::{Affine3, Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::material::Material;
+use crate::math::aabb::Aabb;
+use crate::math::affine::ToAffine3;
+use crate::math::ray::Ray;
+
+/// Everything a primitive knows about where it is and what it looks like,
+/// as opposed to *what* it is.
+///
+/// Primitives embed one of these and hand it out through `Shape::base`, so the
+/// transform bookkeeping lives in exactly one place.
+///
+/// Besides its own transform, a shape inside a `Group` caches the transforms of the whole
+/// parent chain, so points and normals go straight between world and object space.
+#[derive(Debug, Clone)]
+pub struct ShapeBase {
+    transform: Affine3<f32>,
+    inverse: Affine3<f32>,
+    /// Object to world transform of the group this shape is in, identity when it isn't in one
+    parent: Affine3<f32>,
+    /// World to object through every parent
+    world_inverse: Affine3<f32>,
+    material: Material,
+    casts_shadow: bool,
+    /// Whether every parent group casts shadows, so turning a group's shadows off covers its children
+    parent_casts_shadow: bool,
+}
+
+impl ShapeBase {
+    pub fn new() -> Self {
+        Self {
+            transform: Affine3::identity(),
+            inverse: Affine3::identity(),
+            parent: Affine3::identity(),
+            world_inverse: Affine3::identity(),
+            material: Material::default(),
+            casts_shadow: true,
+            parent_casts_shadow: true,
+        }
+    }
+
+    /// The full object to world transform, parents included
+    pub fn world_transform(&self) -> Affine3<f32> {
+        self.parent * self.transform
+    }
+
+    fn set_transform(&mut self, transform: Affine3<f32>) {
+        self.inverse = transform
+            .try_inverse()
+            .expect("a shape's transform must be invertible, it can't scale any axis to zero");
+        self.transform = transform;
+        self.update_world_inverse();
+    }
+
+    fn set_parent(&mut self, parent: Affine3<f32>) {
+        self.parent = parent;
+        self.update_world_inverse();
+    }
+
+    fn update_world_inverse(&mut self) {
+        self.world_inverse = self
+            .world_transform()
+            .try_inverse()
+            .expect("a shape's transform must be invertible, it can't scale any axis to zero");
+    }
+}
+
+impl Default for ShapeBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A renderable primitive.
+///
+/// Implementors only describe the shape in its own object space, through `local_intersect`
+/// and `local_normal_at`. Moving rays into object space and normals back out into world space
+/// is done once, here, by the provided methods.
+pub trait Shape: Debug + Send + Sync {
+    fn base(&self) -> &ShapeBase;
+
+    fn base_mut(&mut self) -> &mut ShapeBase;
+
+    /// Intersect a ray that has already been transformed into object space
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_>;
+
+    /// The surface normal at a point given in object space
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32>;
+
+    /// The surface normal at an object space point, knowing which intersection it came from.
+    ///
+    /// Only shapes that shade using where on their surface they were hit, like smooth
+    /// triangles with the hit's `u` and `v`, need anything more than `local_normal_at`.
+    fn local_normal_at_hit(&self, point: &Point3<f32>, _hit: &Intersection<'_>) -> Vector3<f32> {
+        self.local_normal_at(point)
+    }
+
+    /// The box around the shape in its own object space
+    fn bounds(&self) -> Aabb;
+
+    /// The box around the shape in the space of whatever contains it, a group or the world
+    fn parent_space_bounds(&self) -> Aabb {
+        self.bounds().transform(self.transform())
+    }
+
+    /// The object to world transformation
+    fn transform(&self) -> &Affine3<f32> {
+        &self.base().transform
+    }
+
+    /// The world to object transformation
+    fn inverse(&self) -> &Affine3<f32> {
+        &self.base().inverse
+    }
+
+    /// Place the shape in its parent's space.
+    ///
+    /// # Panics
+    ///
+    /// If the transform can't be inverted, like a scale of zero along some axis.
+    fn set_transform<M: ToAffine3<f32>>(&mut self, transform: M)
+    where
+        Self: Sized,
+    {
+        self.base_mut().set_transform(transform.to_affine3());
+        // Let a group pass its new placement on to its children
+        let parent = self.base().parent;
+        self.set_parent_transform(&parent);
+    }
+
+    /// Tell the shape where the group it was added to sits in the world.
+    ///
+    /// Groups call this on their children, and recurse, whenever they are placed or moved.
+    /// There is no need to call it by hand.
+    fn set_parent_transform(&mut self, parent: &Affine3<f32>) {
+        self.base_mut().set_parent(*parent);
+    }
+
+    /// Bring a world space point into object space, through every parent group
+    fn world_to_object(&self, point: &Point3<f32>) -> Point3<f32> {
+        self.base().world_inverse * point
+    }
+
+    /// Bring an object space normal out into world space, through every parent group
+    fn normal_to_world(&self, normal: &Vector3<f32>) -> Vector3<f32> {
+        // Normals are not points or vectors, they have to stay perpendicular to the surface.
+        // Only the inverse transpose of the linear part does that, translation has to be left out.
+        let inverse_transpose = self.base().world_inverse.matrix().fixed_view::<3, 3>(0, 0).transpose();
+        (inverse_transpose * normal).normalize()
+    }
+
+    fn material(&self) -> &Material {
+        &self.base().material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.base_mut().material
+    }
+
+    fn set_material(&mut self, material: Material) {
+        self.base_mut().material = material;
+    }
+
+    /// Does the shape block light from reaching the objects behind it?
+    ///
+    /// Shapes in a group or csg only do when every parent does too.
+    fn casts_shadow(&self) -> bool {
+        self.base().casts_shadow && self.base().parent_casts_shadow
+    }
+
+    /// Turn shadows off for helper geometry that should stay visible without darkening the scene
+    fn set_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base_mut().casts_shadow = casts_shadow;
+        // Let a group pass the change on to its children
+        let parent = self.base().parent_casts_shadow;
+        self.set_parent_casts_shadow(parent);
+    }
+
+    /// Tell the shape whether the group it was added to casts shadows.
+    ///
+    /// Like `set_parent_transform`, groups call this on their children and there is no need to call it by hand.
+    fn set_parent_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base_mut().parent_casts_shadow = casts_shadow;
+    }
+
+    /// Is `other` this shape, or for groups and csgs, anywhere inside it?
+    fn includes(&self, other: &dyn Shape) -> bool {
+        std::ptr::addr_eq(self, other)
+    }
+
+    /// Intersect a world space ray with the shape
+    fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        self.local_intersect(&ray.transform(self.inverse()))
+    }
+
+    /// The surface normal at a point given in world space
+    ///
+    /// # Panics
+    ///
+    /// Groups and csgs have no surface of their own and panic. Use `normal_at_hit` with one of their
+    /// intersections instead, which knows the child that was hit.
+    fn normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.local_normal_at(&local_point);
+        self.normal_to_world(&local_normal)
+    }
+
+    /// The surface normal at a world space point of the intersection `hit`.
+    ///
+    /// Groups and csgs hand this on to the child `hit` is on.
+    fn normal_at_hit(&self, point: &Point3<f32>, hit: &Intersection<'_>) -> Vector3<f32> {
+        let local_point = self.world_to_object(point);
+        let local_normal = self.local_normal_at_hit(&local_point, hit);
+        self.normal_to_world(&local_normal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use approx::assert_relative_eq;
+    use nalgebra::{Rotation3, Scale3, Translation3};
+    use crate::math::affine::Affine3Ext;
+
+    /// A shape with no geometry of its own, it only records what the provided methods pass to it
+    #[derive(Debug, Default)]
+    struct TestShape {
+        base: ShapeBase,
+        saved_ray: Mutex<Option<Ray>>,
+    }
+
+    impl Shape for TestShape {
+        fn base(&self) -> &ShapeBase {
+            &self.base
+        }
+
+        fn base_mut(&mut self) -> &mut ShapeBase {
+            &mut self.base
+        }
+
+        fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+            *self.saved_ray.lock().unwrap() = Some(*ray);
+            Intersections::default()
+        }
+
+        fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+            point.coords
+        }
+
+        fn bounds(&self) -> Aabb {
+            Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn default_transform() {
+        let s = TestShape::default();
+        assert_relative_eq!(s.transform().matrix(), Affine3::<f32>::identity().matrix());
+    }
+
+    #[test]
+    fn assign_transform() {
+        let mut s = TestShape::default();
+        s.set_transform(Translation3::new(2.0, 3.0, 4.0));
+        assert_relative_eq!(s.transform().matrix(), &Translation3::new(2.0, 3.0, 4.0).to_homogeneous());
+        assert_relative_eq!(s.inverse().matrix(), &Translation3::new(-2.0, -3.0, -4.0).to_homogeneous());
+    }
+
+    #[test]
+    #[should_panic(expected = "must be invertible")]
+    fn singular_transform() {
+        let mut s = TestShape::default();
+        s.set_transform(Scale3::new(1.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn default_material() {
+        let s = TestShape::default();
+        assert_relative_eq!(s.material().ambient, Material::default().ambient);
+    }
+
+    #[test]
+    fn assign_material() {
+        let mut s = TestShape::default();
+        let m = Material {
+            color: crate::canvas::color::Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        };
+        s.set_material(m);
+        assert_relative_eq!(s.material().color, crate::canvas::color::Color::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn casts_shadow() {
+        let mut s = TestShape::default();
+        assert!(s.casts_shadow());
+        s.set_casts_shadow(false);
+        assert!(!s.casts_shadow());
+    }
+
+    #[test]
+    fn intersect_scaled() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+        s.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        s.intersect(&r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_relative_eq!(saved.origin, Point3::new(0.0, 0.0, -2.5));
+        assert_relative_eq!(saved.direction, Vector3::new(0.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn intersect_translated() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+        s.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        s.intersect(&r);
+        let saved = s.saved_ray.lock().unwrap().unwrap();
+        assert_relative_eq!(saved.origin, Point3::new(-5.0, 0.0, -5.0));
+        assert_relative_eq!(saved.direction, Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_translated() {
+        let mut s = TestShape::default();
+        s.set_transform(Translation3::new(0.0, 1.0, 0.0));
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+        let n = s.normal_at(&Point3::new(0.0, 1.0 + k, -k));
+        assert_relative_eq!(n, Vector3::new(0.0, k, -k), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn normal_transformed() {
+        let mut s = TestShape::default();
+        let sc = Affine3::from_scale(Scale3::new(1.0, 0.5, 1.0));
+        let r = Rotation3::from_axis_angle(&Vector3::z_axis(), std::f32::consts::PI / 5.0);
+        s.set_transform(sc * r);
+        let n = s.normal_at(&Point3::new(0.0, f32::sqrt(2.0) / 2.0, -f32::sqrt(2.0) / 2.0));
+        assert_relative_eq!(n, Vector3::new(0.0, 0.97014, -0.24254), epsilon = 1e-5);
+    }
+}
@@ -0,0 +1,246 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::EPSILON;
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::cylinder::check_cap;
+use crate::shape::{Shape, ShapeBase};
+
+/// A double-napped cone around the y axis of its object space: two cones tip to tip at the
+/// origin, with a radius equal to the distance from the origin along y.
+///
+/// Like `Cylinder`, it can be cut along y with `minimum` and `maximum`, and `closed` with caps.
+#[derive(Debug, Clone)]
+pub struct Cone {
+    base: ShapeBase,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+}
+
+impl Cone {
+    pub fn new() -> Self {
+        Self {
+            base: ShapeBase::new(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    /// A cone cut between `minimum` and `maximum` along y, capped when `closed`
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..Self::new()
+        }
+    }
+
+    /// Intersections with the end caps, if the cone has any
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        if !self.closed || ray.direction.y.abs() < EPSILON * ray.direction.norm() {
+            return;
+        }
+
+        // A cap is as wide as the cone is at that height
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Cone {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Vec::new();
+        let (o, d) = (ray.origin, ray.direction);
+
+        // Solve x^2 - y^2 + z^2 = 0 along the ray. `a` is quadratic and `b` linear in the direction,
+        // which is far from unit length on scaled cones, so the tolerances scale along with them.
+        let a = d.x * d.x - d.y * d.y + d.z * d.z;
+        let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+        let c = o.x * o.x - o.y * o.y + o.z * o.z;
+        let length_squared = d.norm_squared();
+
+        let ts = if a.abs() < EPSILON * length_squared {
+            // The ray is parallel to one of the halves: it crosses the other one exactly once,
+            // unless it also goes through the tip (b = 0) and misses entirely
+            if b.abs() < EPSILON * length_squared.sqrt() {
+                vec![]
+            } else {
+                vec![-c / (2.0 * b)]
+            }
+        } else {
+            // Rays grazing the wall can land a hair below zero, they still touch the cone
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < -EPSILON * length_squared {
+                vec![]
+            } else {
+                let sqrt_disc = discriminant.max(0.0).sqrt();
+                vec![(-b - sqrt_disc) / (2.0 * a), (-b + sqrt_disc) / (2.0 * a)]
+            }
+        };
+
+        for t in ts {
+            let y = o.y + t * d.y;
+            if self.minimum < y && y < self.maximum {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn bounds(&self) -> Aabb {
+        // As wide as the cone gets at whichever end is further from the tip
+        let radius = self.minimum.abs().max(self.maximum.abs());
+        Aabb::new(
+            Point3::new(-radius, self.minimum, -radius),
+            Point3::new(radius, self.maximum, radius),
+        )
+    }
+
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        let dist = point.x.powi(2) + point.z.powi(2);
+        if dist < point.y.powi(2) && point.y >= self.maximum - EPSILON {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else if dist < point.y.powi(2) && point.y <= self.minimum + EPSILON {
+            Vector3::new(0.0, -1.0, 0.0)
+        } else {
+            // The wall slopes at 45 degrees, so the normal leans away from the tip by the radius
+            let y = dist.sqrt();
+            let y = if point.y > 0.0 { -y } else { y };
+            Vector3::new(point.x, y, point.z)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn hits() {
+        let cone = Cone::new();
+        let cases = [
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(1.0, 1.0, 1.0), 8.66025, 8.66025),
+            (Point3::new(1.0, 1.0, -5.0), Vector3::new(-0.5, -1.0, 1.0), 4.55006, 49.44994),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cone.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert_relative_eq!(xs[0].t, t0, epsilon = 1e-3);
+            assert_relative_eq!(xs[1].t, t1, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn scaled_up() {
+        use nalgebra::Scale3;
+
+        // The object space direction is 1/200 long, `a` alone would be far below EPSILON
+        let mut cone = Cone::new();
+        cone.set_transform(Scale3::new(200.0, 200.0, 200.0));
+        let r = Ray::new(Point3::new(200.0, 200.0, -1000.0), Vector3::new(-0.5, -1.0, 1.0).normalize());
+        let xs = cone.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 910.012, max_relative = 1e-4);
+        assert_relative_eq!(xs[1].t, 9889.988, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn parallel_to_one_half() {
+        let cone = Cone::new();
+        let r = Ray::new(Point3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 1.0, 1.0).normalize());
+        let xs = cone.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 0.35355, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn through_the_tip_along_a_half() {
+        let cone = Cone::new();
+        let r = Ray::new(Point3::new(0.0, -1.0, -1.0), Vector3::new(0.0, 1.0, 1.0).normalize());
+        assert!(cone.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn caps() {
+        let cone = Cone::truncated(-0.5, 0.5, true);
+        let cases = [
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0), 0),
+            (Point3::new(0.0, 0.0, -0.25), Vector3::new(0.0, 1.0, 1.0), 2),
+            (Point3::new(0.0, 0.0, -0.25), Vector3::new(0.0, 1.0, 0.0), 4),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cone.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn normals() {
+        let cone = Cone::new();
+        let cases = [
+            (Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 0.0)),
+            (Point3::new(1.0, 1.0, 1.0), Vector3::new(1.0, -f32::sqrt(2.0), 1.0)),
+            (Point3::new(-1.0, -1.0, 0.0), Vector3::new(-1.0, 1.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_relative_eq!(cone.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn cap_normals() {
+        let cone = Cone::truncated(-1.0, 2.0, true);
+        assert_relative_eq!(cone.local_normal_at(&Point3::new(0.5, 2.0, 0.5)), Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(cone.local_normal_at(&Point3::new(0.2, -1.0, 0.3)), Vector3::new(0.0, -1.0, 0.0));
+    }
+
+    #[test]
+    fn lamp_shade() {
+        use nalgebra::Translation3;
+
+        // An open, truncated cone standing on the floor: the ray passes the wall twice
+        let mut cone = Cone::truncated(0.5, 1.5, false);
+        cone.set_transform(Translation3::new(0.0, 2.0, 0.0));
+        let r = Ray::new(Point3::new(0.0, 3.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = cone.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 4.0, epsilon = 1e-4);
+        assert_relative_eq!(xs[1].t, 6.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn bounds() {
+        let b = Cone::new().bounds();
+        assert!(!b.is_finite());
+
+        let b = Cone::truncated(-5.0, 3.0, false).bounds();
+        assert_relative_eq!(b.min, Point3::new(-5.0, -5.0, -5.0));
+        assert_relative_eq!(b.max, Point3::new(5.0, 3.0, 5.0));
+    }
+}
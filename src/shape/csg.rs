@@ -0,0 +1,324 @@
+use nalgebra::{Affine3, Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// How a `Csg` combines its two operands
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CsgOperation {
+    /// Everything in either operand
+    Union,
+    /// Only what is in both operands
+    Intersection,
+    /// The left operand with the right one cut out of it
+    Difference,
+}
+
+impl CsgOperation {
+    /// Is a hit on the surface of the combined shape?
+    ///
+    /// `left_hit` says which operand was hit, `in_left` and `in_right` whether the ray is
+    /// inside each operand at that point.
+    pub fn allows(&self, left_hit: bool, in_left: bool, in_right: bool) -> bool {
+        match self {
+            // Surfaces not inside the other operand
+            Self::Union => (left_hit && !in_right) || (!left_hit && !in_left),
+            // Surfaces inside the other operand
+            Self::Intersection => (left_hit && in_right) || (!left_hit && in_left),
+            // The left surface outside the right operand, and the right surface inside the left one
+            Self::Difference => (left_hit && !in_right) || (!left_hit && in_left),
+        }
+    }
+}
+
+/// Constructive solid geometry: two shapes combined into one by a boolean operation.
+///
+/// Either operand can be a group, or another `Csg`, and both are placed relative to the `Csg`
+/// just like a group's children. Intersections name the operand's primitive that was hit.
+#[derive(Debug)]
+pub struct Csg {
+    base: ShapeBase,
+    operation: CsgOperation,
+    left: Box<dyn Shape>,
+    right: Box<dyn Shape>,
+}
+
+impl Csg {
+    pub fn new<L: Shape + 'static, R: Shape + 'static>(operation: CsgOperation, left: L, right: R) -> Self {
+        Self::from_boxed(operation, Box::new(left), Box::new(right))
+    }
+
+    /// `new` for operands that are already boxed
+    pub fn from_boxed(operation: CsgOperation, mut left: Box<dyn Shape>, mut right: Box<dyn Shape>) -> Self {
+        let base = ShapeBase::new();
+        left.set_parent_transform(&base.world_transform());
+        right.set_parent_transform(&base.world_transform());
+        Self { base, operation, left, right }
+    }
+
+    pub fn union<L: Shape + 'static, R: Shape + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Union, left, right)
+    }
+
+    pub fn intersection<L: Shape + 'static, R: Shape + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Intersection, left, right)
+    }
+
+    pub fn difference<L: Shape + 'static, R: Shape + 'static>(left: L, right: R) -> Self {
+        Self::new(CsgOperation::Difference, left, right)
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &dyn Shape {
+        self.left.as_ref()
+    }
+
+    pub fn right(&self) -> &dyn Shape {
+        self.right.as_ref()
+    }
+
+    /// Keep only the intersections, sorted, that are on the surface of the combined shape
+    pub fn filter_intersections<'a>(&self, xs: Intersections<'a>) -> Intersections<'a> {
+        // Walk along the ray, flipping in and out of an operand at every one of its hits
+        let mut in_left = false;
+        let mut in_right = false;
+        let mut result = Vec::new();
+        for i in xs {
+            let left_hit = self.left.includes(i.object);
+            if self.operation.allows(left_hit, in_left, in_right) {
+                result.push(i);
+            }
+            if left_hit {
+                in_left = !in_left;
+            } else {
+                in_right = !in_right;
+            }
+        }
+        Intersections::new(result)
+    }
+}
+
+impl Shape for Csg {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn set_parent_transform(&mut self, parent: &Affine3<f32>) {
+        self.base.set_parent(*parent);
+        let world = self.base.world_transform();
+        self.left.set_parent_transform(&world);
+        self.right.set_parent_transform(&world);
+    }
+
+    fn set_parent_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.parent_casts_shadow = casts_shadow;
+        let casts_shadow = self.casts_shadow();
+        self.left.set_parent_casts_shadow(casts_shadow);
+        self.right.set_parent_casts_shadow(casts_shadow);
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        // The ray is in csg space, which is each operand's parent space
+        let xs = self.left.intersect(ray).into_iter().chain(self.right.intersect(ray)).collect();
+        self.filter_intersections(xs)
+    }
+
+    fn bounds(&self) -> Aabb {
+        let left = self.left.parent_space_bounds();
+        match self.operation {
+            // Nothing of the right operand is left over outside the left one
+            CsgOperation::Difference => left,
+            _ => left.merge(&self.right.parent_space_bounds()),
+        }
+    }
+
+    fn local_normal_at(&self, _point: &Point3<f32>) -> Vector3<f32> {
+        panic!("a csg has no surface of its own, use `normal_at_hit` to get the normal of the operand that was hit")
+    }
+
+    fn normal_at_hit(&self, point: &Point3<f32>, hit: &Intersection<'_>) -> Vector3<f32> {
+        // Intersections of a csg are always on one of its operands, which can take it from here
+        debug_assert!(self.includes(hit.object), "the hit is not on this csg");
+        hit.object.normal_at_hit(point, hit)
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.left.includes(other) || self.right.includes(other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{Scale3, Translation3};
+    use crate::intersection::Intersection;
+    use crate::math::affine::Affine3Ext;
+    use crate::shape::cube::Cube;
+    use crate::shape::cylinder::Cylinder;
+    use crate::shape::group::Group;
+    use crate::shape::sphere::Sphere;
+
+    #[test]
+    fn new() {
+        let c = Csg::union(Sphere::new(), Cube::new());
+        assert_eq!(c.operation(), CsgOperation::Union);
+        assert_relative_eq!(c.bounds().max, Point3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn rules() {
+        use CsgOperation::*;
+        // operation, left_hit, in_left, in_right, allowed
+        let cases = [
+            (Union, true, true, true, false),
+            (Union, true, true, false, true),
+            (Union, true, false, true, false),
+            (Union, true, false, false, true),
+            (Union, false, true, true, false),
+            (Union, false, true, false, false),
+            (Union, false, false, true, true),
+            (Union, false, false, false, true),
+            (Intersection, true, true, true, true),
+            (Intersection, true, true, false, false),
+            (Intersection, true, false, true, true),
+            (Intersection, true, false, false, false),
+            (Intersection, false, true, true, true),
+            (Intersection, false, true, false, true),
+            (Intersection, false, false, true, false),
+            (Intersection, false, false, false, false),
+            (Difference, true, true, true, false),
+            (Difference, true, true, false, true),
+            (Difference, true, false, true, false),
+            (Difference, true, false, false, true),
+            (Difference, false, true, true, true),
+            (Difference, false, true, false, true),
+            (Difference, false, false, true, false),
+            (Difference, false, false, false, false),
+        ];
+        for (op, left_hit, in_left, in_right, allowed) in cases {
+            assert_eq!(op.allows(left_hit, in_left, in_right), allowed, "{op:?} {left_hit} {in_left} {in_right}");
+        }
+    }
+
+    #[test]
+    fn filter() {
+        // Which of the four intersections, alternating between the operands, survive
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+        for (op, x0, x1) in cases {
+            let c = Csg::new(op, Sphere::new(), Cube::new());
+            let xs = Intersections::new(vec![
+                Intersection::new(1.0, c.left()),
+                Intersection::new(2.0, c.right()),
+                Intersection::new(3.0, c.left()),
+                Intersection::new(4.0, c.right()),
+            ]);
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(result.len(), 2);
+            assert_eq!(result[0], xs[x0], "{op:?}");
+            assert_eq!(result[1], xs[x1], "{op:?}");
+        }
+    }
+
+    #[test]
+    fn ray_misses() {
+        let c = Csg::union(Sphere::new(), Cube::new());
+        let r = Ray::new(Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(c.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn ray_hits() {
+        let mut s2 = Sphere::new();
+        s2.set_transform(Translation3::new(0.0, 0.0, 0.5));
+        let c = Csg::union(Sphere::new(), s2);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = c.local_intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 4.0);
+        assert!(std::ptr::addr_eq(xs[0].object, c.left()));
+        assert_relative_eq!(xs[1].t, 6.5);
+        assert!(std::ptr::addr_eq(xs[1].object, c.right()));
+    }
+
+    #[test]
+    fn drilled_cube() {
+        let mut drill = Cylinder::truncated(-2.0, 2.0, true);
+        drill.set_transform(Affine3::from_scale(Scale3::new(0.5, 1.0, 0.5)));
+        let c = Csg::difference(Cube::new(), drill);
+
+        // Straight down the hole
+        let r = Ray::new(Point3::new(0.0, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        assert!(c.intersect(&r).is_empty());
+
+        // Through the side, into the hole and out again
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let ts: Vec<f32> = c.intersect(&r).iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 4);
+        for (t, expected) in ts.iter().zip([4.0, 4.5, 5.5, 6.0]) {
+            assert_relative_eq!(*t, expected, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn normal_at_hit_goes_to_the_operand() {
+        let c = Csg::difference(Cube::new(), Sphere::new());
+        let r = Ray::new(Point3::new(-5.0, 0.9, 0.9), Vector3::new(1.0, 0.0, 0.0));
+        let xs = c.intersect(&r);
+        let hit = xs.hit().unwrap();
+        assert!(std::ptr::addr_eq(hit.object, c.left()));
+        let n = c.normal_at_hit(&r.position(hit.t), hit);
+        assert_relative_eq!(n, Vector3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "no surface of its own")]
+    fn normal_at_panics() {
+        Csg::union(Sphere::new(), Cube::new()).normal_at(&Point3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn group_operands() {
+        // A group on the left, everything in it counts as the left operand
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(3.0, 0.0, 0.0));
+        g.add_child(s);
+        // A box from x = -2 to 3, around all of the first sphere and half of the second
+        let mut b = Cube::new();
+        b.set_transform(Translation3::new(0.5, 0.0, 0.0) * Affine3::from_scale(Scale3::new(2.5, 2.0, 2.0)));
+        let c = Csg::intersection(g, b);
+        assert!(!c.right().includes(c.left()));
+
+        let r = Ray::new(Point3::new(-5.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0));
+        let xs = c.intersect(&r);
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 6.0, 7.0, 8.0]);
+        assert!(xs[..3].iter().all(|i| c.left().includes(i.object)));
+        assert!(c.right().includes(xs[3].object));
+    }
+
+    #[test]
+    fn moving_the_csg_moves_operands() {
+        let mut c = Csg::union(Sphere::new(), Cube::new());
+        c.set_transform(Translation3::new(0.0, 10.0, 0.0));
+        let r = Ray::new(Point3::new(0.0, 10.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        let n = xs[0].object.normal_at(&r.position(xs[0].t));
+        assert_relative_eq!(n, Vector3::new(0.0, 0.0, -1.0));
+    }
+}
@@ -0,0 +1,191 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// An axis-aligned cube from -1 to 1 on every axis of its object space.
+///
+/// Stretch it with `Affine3Ext::from_scale` or `from_shear` for boxes, tables and rooms.
+#[derive(Debug, Clone, Default)]
+pub struct Cube {
+    base: ShapeBase,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Shape for Cube {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        // The cube is exactly its own bounding box
+        match self.bounds().intersect(ray) {
+            Some((tmin, tmax)) => Intersections::new(vec![
+                Intersection::new(tmin, self),
+                Intersection::new(tmax, self),
+            ]),
+            None => Intersections::default(),
+        }
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        // The face a point is on is the axis it is furthest along.
+        // Edges and corners are on several faces at once, x wins over y wins over z.
+        let (x, y, z) = (point.x.abs(), point.y.abs(), point.z.abs());
+        if x >= y && x >= z {
+            Vector3::new(point.x.signum(), 0.0, 0.0)
+        } else if y >= z {
+            Vector3::new(0.0, point.y.signum(), 0.0)
+        } else {
+            Vector3::new(0.0, 0.0, point.z.signum())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn intersect_faces() {
+        let c = Cube::new();
+        let cases = [
+            // +x, -x, +y, -y, +z, -z, inside
+            (Point3::new(5.0, 0.5, 0.0), Vector3::new(-1.0, 0.0, 0.0), 4.0, 6.0),
+            (Point3::new(-5.0, 0.5, 0.0), Vector3::new(1.0, 0.0, 0.0), 4.0, 6.0),
+            (Point3::new(0.5, 5.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 4.0, 6.0),
+            (Point3::new(0.5, -5.0, 0.0), Vector3::new(0.0, 1.0, 0.0), 4.0, 6.0),
+            (Point3::new(0.5, 0.0, 5.0), Vector3::new(0.0, 0.0, -1.0), 4.0, 6.0),
+            (Point3::new(0.5, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Point3::new(0.0, 0.5, 0.0), Vector3::new(0.0, 0.0, 1.0), -1.0, 1.0),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let xs = c.local_intersect(&Ray::new(origin, direction));
+            assert_eq!(xs.len(), 2);
+            assert_relative_eq!(xs[0].t, t1);
+            assert_relative_eq!(xs[1].t, t2);
+        }
+    }
+
+    #[test]
+    fn misses() {
+        let c = Cube::new();
+        let cases = [
+            (Point3::new(-2.0, 0.0, 0.0), Vector3::new(0.2673, 0.5345, 0.8018)),
+            (Point3::new(0.0, -2.0, 0.0), Vector3::new(0.8018, 0.2673, 0.5345)),
+            (Point3::new(0.0, 0.0, -2.0), Vector3::new(0.5345, 0.8018, 0.2673)),
+            (Point3::new(2.0, 0.0, 2.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Point3::new(0.0, 2.0, 2.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(2.0, 2.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)),
+        ];
+        for (origin, direction) in cases {
+            assert!(c.local_intersect(&Ray::new(origin, direction)).is_empty());
+        }
+    }
+
+    #[test]
+    fn through_corners() {
+        let c = Cube::new();
+        let xs = c.local_intersect(&Ray::new(Point3::new(-2.0, -2.0, -2.0), Vector3::new(1.0, 1.0, 1.0)));
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 1.0);
+        assert_relative_eq!(xs[1].t, 3.0);
+    }
+
+    #[test]
+    fn grazes_edge() {
+        // Runs exactly along the edge where the top and front faces meet
+        let c = Cube::new();
+        let xs = c.local_intersect(&Ray::new(Point3::new(-3.0, 1.0, -1.0), Vector3::new(1.0, 0.0, 0.0)));
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 2.0);
+        assert_relative_eq!(xs[1].t, 4.0);
+    }
+
+    #[test]
+    fn grazes_face() {
+        let c = Cube::new();
+        let xs = c.local_intersect(&Ray::new(Point3::new(0.0, 1.0, -3.0), Vector3::new(0.0, 0.0, 1.0)));
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 2.0);
+        assert_relative_eq!(xs[1].t, 4.0);
+
+        // just above it is a miss
+        let xs = c.local_intersect(&Ray::new(Point3::new(0.0, 1.001, -3.0), Vector3::new(0.0, 0.0, 1.0)));
+        assert!(xs.is_empty());
+    }
+
+    #[test]
+    fn normals() {
+        let c = Cube::new();
+        let cases = [
+            (Point3::new(1.0, 0.5, -0.8), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(-1.0, -0.2, 0.9), Vector3::new(-1.0, 0.0, 0.0)),
+            (Point3::new(-0.4, 1.0, -0.1), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.3, -1.0, -0.7), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(-0.6, 0.3, 1.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(0.4, 0.4, -1.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Point3::new(1.0, 1.0, 1.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(-1.0, -1.0, -1.0), Vector3::new(-1.0, 0.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_relative_eq!(c.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn normal_on_edges() {
+        let c = Cube::new();
+        assert_relative_eq!(c.local_normal_at(&Point3::new(0.0, 1.0, -1.0)), Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(c.local_normal_at(&Point3::new(-1.0, 0.0, 1.0)), Vector3::new(-1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn stretched_into_a_table_top() {
+        use nalgebra::{Affine3, Scale3, Translation3};
+        use crate::math::affine::Affine3Ext;
+
+        let mut c = Cube::new();
+        c.set_transform(Translation3::new(0.0, 3.0, 0.0) * Affine3::from_scale(Scale3::new(2.0, 0.1, 1.0)));
+        let xs = c.intersect(&Ray::new(Point3::new(1.5, 10.0, 0.0), Vector3::new(0.0, -1.0, 0.0)));
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 6.9, epsilon = 1e-5);
+        assert_relative_eq!(xs[1].t, 7.1, epsilon = 1e-5);
+        assert_relative_eq!(c.normal_at(&Point3::new(1.5, 3.1, 0.0)), Vector3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn scaled_into_a_room() {
+        use nalgebra::Scale3;
+
+        // A shallow ray into a huge cube, its object space direction is far below EPSILON on every axis
+        let mut c = Cube::new();
+        c.set_transform(Scale3::new(10000.0, 10000.0, 10000.0));
+        let xs = c.intersect(&Ray::new(Point3::new(0.5, 20000.0, -20000.0), Vector3::new(0.0, -0.5, 0.866)));
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 20000.0, max_relative = 1e-4);
+        assert_relative_eq!(xs[1].t, 34642.0, max_relative = 1e-4);
+    }
+
+    #[test]
+    fn bounds() {
+        let b = Cube::new().bounds();
+        assert_relative_eq!(b.min, Point3::new(-1.0, -1.0, -1.0));
+        assert_relative_eq!(b.max, Point3::new(1.0, 1.0, 1.0));
+    }
+}
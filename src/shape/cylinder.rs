@@ -0,0 +1,262 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::EPSILON;
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// A cylinder of radius 1 around the y axis of its object space.
+///
+/// Infinitely long by default; `minimum` and `maximum` cut it along y (both ends excluded),
+/// and a `closed` cylinder gets flat caps at those cuts.
+#[derive(Debug, Clone)]
+pub struct Cylinder {
+    base: ShapeBase,
+    pub minimum: f32,
+    pub maximum: f32,
+    pub closed: bool,
+}
+
+impl Cylinder {
+    pub fn new() -> Self {
+        Self {
+            base: ShapeBase::new(),
+            minimum: f32::NEG_INFINITY,
+            maximum: f32::INFINITY,
+            closed: false,
+        }
+    }
+
+    /// A cylinder cut between `minimum` and `maximum` along y, capped when `closed`
+    pub fn truncated(minimum: f32, maximum: f32, closed: bool) -> Self {
+        Self {
+            minimum,
+            maximum,
+            closed,
+            ..Self::new()
+        }
+    }
+
+    /// Intersections with the end caps, if the cylinder has any
+    fn intersect_caps<'a>(&'a self, ray: &Ray, xs: &mut Vec<Intersection<'a>>) {
+        // Caps only exist on closed cylinders, and a ray parallel to them can't hit them
+        if !self.closed || ray.direction.y.abs() < EPSILON * ray.direction.norm() {
+            return;
+        }
+
+        for y in [self.minimum, self.maximum] {
+            let t = (y - ray.origin.y) / ray.direction.y;
+            if check_cap(ray, t, 1.0) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shape for Cylinder {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        let mut xs = Vec::new();
+
+        // Solve x^2 + z^2 = 1 along the ray. A ray parallel to the y axis can only hit the caps.
+        // `a` grows with the square of the direction, which is far from unit length on scaled cylinders.
+        let a = ray.direction.x.powi(2) + ray.direction.z.powi(2);
+        if a.abs() >= EPSILON * ray.direction.norm_squared() {
+            let b = 2.0 * ray.origin.x * ray.direction.x + 2.0 * ray.origin.z * ray.direction.z;
+            let c = ray.origin.x.powi(2) + ray.origin.z.powi(2) - 1.0;
+            let discriminant = b * b - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return Intersections::default();
+            }
+
+            let sqrt_disc = discriminant.sqrt();
+            for t in [(-b - sqrt_disc) / (2.0 * a), (-b + sqrt_disc) / (2.0 * a)] {
+                let y = ray.origin.y + t * ray.direction.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(ray, &mut xs);
+        Intersections::new(xs)
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(
+            Point3::new(-1.0, self.minimum, -1.0),
+            Point3::new(1.0, self.maximum, 1.0),
+        )
+    }
+
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        // Points on a cap are within the radius and at one of the ends
+        let dist = point.x.powi(2) + point.z.powi(2);
+        if dist < 1.0 && point.y >= self.maximum - EPSILON {
+            Vector3::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && point.y <= self.minimum + EPSILON {
+            Vector3::new(0.0, -1.0, 0.0)
+        } else {
+            Vector3::new(point.x, 0.0, point.z)
+        }
+    }
+}
+
+/// Is the point at `t` along the ray within `radius` of the y axis?
+pub(super) fn check_cap(ray: &Ray, t: f32, radius: f32) -> bool {
+    let x = ray.origin.x + t * ray.direction.x;
+    let z = ray.origin.z + t * ray.direction.z;
+    // A little slack, so rays leaving exactly through the rim still count
+    x * x + z * z <= radius * radius + EPSILON
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn misses() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Point3::new(1.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(1.0, 1.0, 1.0)),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert!(cyl.local_intersect(&r).is_empty());
+        }
+    }
+
+    #[test]
+    fn hits() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Point3::new(1.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 5.0, 5.0),
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 4.0, 6.0),
+            (Point3::new(0.5, 0.0, -5.0), Vector3::new(0.1, 1.0, 1.0), 6.80798, 7.08872),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            let xs = cyl.local_intersect(&r);
+            assert_eq!(xs.len(), 2);
+            assert_relative_eq!(xs[0].t, t0, epsilon = 1e-4);
+            assert_relative_eq!(xs[1].t, t1, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn scaled_up() {
+        use nalgebra::Scale3;
+
+        // Head on through the center, with an object space direction 1/200 long
+        let mut cyl = Cylinder::new();
+        cyl.set_transform(Scale3::new(200.0, 200.0, 200.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, -1000.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = cyl.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 800.0, max_relative = 1e-5);
+        assert_relative_eq!(xs[1].t, 1200.0, max_relative = 1e-5);
+
+        // Still parallel to the axis when it is
+        let r = Ray::new(Point3::new(0.0, -1000.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        assert!(cyl.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn normals() {
+        let cyl = Cylinder::new();
+        let cases = [
+            (Point3::new(1.0, 0.0, 0.0), Vector3::new(1.0, 0.0, 0.0)),
+            (Point3::new(0.0, 5.0, -1.0), Vector3::new(0.0, 0.0, -1.0)),
+            (Point3::new(0.0, -2.0, 1.0), Vector3::new(0.0, 0.0, 1.0)),
+            (Point3::new(-1.0, 1.0, 0.0), Vector3::new(-1.0, 0.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_relative_eq!(cyl.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn default_bounds() {
+        let cyl = Cylinder::new();
+        assert_eq!(cyl.minimum, f32::NEG_INFINITY);
+        assert_eq!(cyl.maximum, f32::INFINITY);
+        assert!(!cyl.closed);
+    }
+
+    #[test]
+    fn truncated() {
+        let cyl = Cylinder::truncated(1.0, 2.0, false);
+        let cases = [
+            (Point3::new(0.0, 1.5, 0.0), Vector3::new(0.1, 1.0, 0.0), 0),
+            (Point3::new(0.0, 3.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 0),
+            (Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 0),
+            (Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 0),
+            (Point3::new(0.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 1.0), 0),
+            (Point3::new(0.0, 1.5, -2.0), Vector3::new(0.0, 0.0, 1.0), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn caps() {
+        let cyl = Cylinder::truncated(1.0, 2.0, true);
+        let cases = [
+            (Point3::new(0.0, 3.0, 0.0), Vector3::new(0.0, -1.0, 0.0), 2),
+            (Point3::new(0.0, 3.0, -2.0), Vector3::new(0.0, -1.0, 2.0), 2),
+            // corner case: exits through the edge of a cap
+            (Point3::new(0.0, 4.0, -2.0), Vector3::new(0.0, -1.0, 1.0), 2),
+            (Point3::new(0.0, 0.0, -2.0), Vector3::new(0.0, 1.0, 2.0), 2),
+            (Point3::new(0.0, -1.0, -2.0), Vector3::new(0.0, 1.0, 1.0), 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(origin, direction.normalize());
+            assert_eq!(cyl.local_intersect(&r).len(), count);
+        }
+    }
+
+    #[test]
+    fn cap_normals() {
+        let cyl = Cylinder::truncated(1.0, 2.0, true);
+        let cases = [
+            (Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(0.5, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(0.0, 1.0, 0.5), Vector3::new(0.0, -1.0, 0.0)),
+            (Point3::new(0.0, 2.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.5, 2.0, 0.0), Vector3::new(0.0, 1.0, 0.0)),
+            (Point3::new(0.0, 2.0, 0.5), Vector3::new(0.0, 1.0, 0.0)),
+        ];
+        for (point, normal) in cases {
+            assert_relative_eq!(cyl.local_normal_at(&point), normal);
+        }
+    }
+
+    #[test]
+    fn bounds() {
+        let b = Cylinder::new().bounds();
+        assert_eq!(b.min, Point3::new(-1.0, f32::NEG_INFINITY, -1.0));
+        assert_eq!(b.max, Point3::new(1.0, f32::INFINITY, 1.0));
+
+        let b = Cylinder::truncated(-5.0, 3.0, false).bounds();
+        assert_relative_eq!(b.min, Point3::new(-1.0, -5.0, -1.0));
+        assert_relative_eq!(b.max, Point3::new(1.0, 3.0, 1.0));
+    }
+}
@@ -0,0 +1,320 @@
+use std::sync::OnceLock;
+use nalgebra::{Affine3, Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::aabb::Aabb;
+use crate::math::bvh::Bvh;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// A collection of shapes that are transformed together, like a node of a scene graph.
+///
+/// Children are positioned relative to the group, so moving the group moves all of them,
+/// and groups can be nested. Intersections always name the child that was hit, never the group.
+///
+/// Rays are only tested against the children whose boxes they go through, found with a
+/// bounding volume hierarchy that is built on the first intersection after children change.
+#[derive(Debug, Default)]
+pub struct Group {
+    base: ShapeBase,
+    children: Vec<Box<dyn Shape>>,
+    /// Around every child, in group space
+    bounds: Aabb,
+    bvh: OnceLock<Bvh>,
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_child<S: Shape + 'static>(&mut self, child: S) {
+        self.add_boxed_child(Box::new(child));
+    }
+
+    /// `add_child` for a shape that is already boxed, e.g. taken out of another collection
+    pub fn add_boxed_child(&mut self, mut child: Box<dyn Shape>) {
+        child.set_parent_transform(&self.base.world_transform());
+        child.set_parent_casts_shadow(self.casts_shadow());
+        self.bounds.add_box(&child.parent_space_bounds());
+        self.children.push(child);
+        self.bvh = OnceLock::new();
+    }
+
+    pub fn children(&self) -> &[Box<dyn Shape>] {
+        &self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let bounds: Vec<Aabb> = self.children.iter().map(|child| child.parent_space_bounds()).collect();
+            Bvh::build(&bounds)
+        })
+    }
+}
+
+impl Shape for Group {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn set_parent_transform(&mut self, parent: &Affine3<f32>) {
+        self.base.set_parent(*parent);
+        let world = self.base.world_transform();
+        for child in &mut self.children {
+            child.set_parent_transform(&world);
+        }
+    }
+
+    fn set_parent_casts_shadow(&mut self, casts_shadow: bool) {
+        self.base.parent_casts_shadow = casts_shadow;
+        let casts_shadow = self.casts_shadow();
+        for child in &mut self.children {
+            child.set_parent_casts_shadow(casts_shadow);
+        }
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        // The ray is in group space, which is each child's parent space
+        let mut xs = Vec::new();
+        self.bvh().query(ray, |i| xs.extend(self.children[i].intersect(ray)));
+        Intersections::new(xs)
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.bounds
+    }
+
+    fn local_normal_at(&self, _point: &Point3<f32>) -> Vector3<f32> {
+        panic!("a group has no surface of its own, use `normal_at_hit` to get the normal of the child that was hit")
+    }
+
+    fn normal_at_hit(&self, point: &Point3<f32>, hit: &Intersection<'_>) -> Vector3<f32> {
+        // Intersections of a group are always on one of its children, which can take it from here
+        debug_assert!(self.includes(hit.object), "the hit is not on this group");
+        hit.object.normal_at_hit(point, hit)
+    }
+
+    fn includes(&self, other: &dyn Shape) -> bool {
+        self.children.iter().any(|child| child.includes(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::FRAC_PI_2;
+    use approx::assert_relative_eq;
+    use nalgebra::{Rotation3, Scale3, Translation3};
+    use crate::math::affine::Affine3Ext;
+    use crate::shape::cube::Cube;
+    use crate::shape::plane::Plane;
+    use crate::shape::sphere::Sphere;
+
+    #[test]
+    fn new() {
+        let g = Group::new();
+        assert_relative_eq!(g.transform().matrix(), Affine3::identity().matrix());
+        assert!(g.is_empty());
+    }
+
+    #[test]
+    fn add_child() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        assert_eq!(g.children().len(), 1);
+    }
+
+    #[test]
+    fn intersect_empty() {
+        let g = Group::new();
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(g.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn intersect_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_transform(Translation3::new(0.0, 0.0, -3.0));
+        g.add_child(s2);
+        let mut s3 = Sphere::new();
+        s3.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        g.add_child(s3);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = g.local_intersect(&r);
+        assert_eq!(xs.len(), 4);
+        let s1 = g.children()[0].as_ref();
+        let s2 = g.children()[1].as_ref();
+        assert!(std::ptr::addr_eq(xs[0].object, s2));
+        assert!(std::ptr::addr_eq(xs[1].object, s2));
+        assert!(std::ptr::addr_eq(xs[2].object, s1));
+        assert!(std::ptr::addr_eq(xs[3].object, s1));
+    }
+
+    #[test]
+    fn intersect_transformed() {
+        let mut g = Group::new();
+        g.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        g.add_child(s);
+
+        let r = Ray::new(Point3::new(10.0, 0.0, -10.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(g.intersect(&r).len(), 2);
+    }
+
+    fn nested(scale: Scale3<f32>) -> Group {
+        let mut g1 = Group::new();
+        g1.set_transform(Rotation3::from_axis_angle(&Vector3::y_axis(), FRAC_PI_2));
+        let mut g2 = Group::new();
+        g2.set_transform(scale);
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        g2.add_child(s);
+        g1.add_child(g2);
+        g1
+    }
+
+    /// The sphere deep inside `nested`, found the way the renderer would: by hitting it
+    fn nested_sphere(g1: &Group) -> &dyn Shape {
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+        g1.intersect(&r).hit().unwrap().object
+    }
+
+    #[test]
+    fn world_to_object() {
+        let g1 = nested(Scale3::new(2.0, 2.0, 2.0));
+        let s = nested_sphere(&g1);
+        let p = s.world_to_object(&Point3::new(-2.0, 0.0, -10.0));
+        assert_relative_eq!(p, Point3::new(0.0, 0.0, -1.0), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn normal_to_world() {
+        let g1 = nested(Scale3::new(1.0, 2.0, 3.0));
+        let s = nested_sphere(&g1);
+        let k = f32::sqrt(3.0) / 3.0;
+        let n = s.normal_to_world(&Vector3::new(k, k, k));
+        assert_relative_eq!(n, Vector3::new(0.2857, 0.4286, -0.8571), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn normal_on_child() {
+        let g1 = nested(Scale3::new(1.0, 2.0, 3.0));
+        let s = nested_sphere(&g1);
+        let n = s.normal_at(&Point3::new(1.7321, 1.1547, -5.5774));
+        assert_relative_eq!(n, Vector3::new(0.2857, 0.4286, -0.8571), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn normal_at_hit_goes_to_the_child() {
+        let g1 = nested(Scale3::new(1.0, 2.0, 3.0));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, -1.0));
+        let xs = g1.intersect(&r);
+        let hit = xs.hit().unwrap();
+        let p = r.position(hit.t);
+        assert_relative_eq!(g1.normal_at_hit(&p, hit), hit.object.normal_at(&p));
+    }
+
+    #[test]
+    #[should_panic(expected = "no surface of its own")]
+    fn normal_at_panics() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.normal_at(&Point3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn moving_the_group_moves_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.set_transform(Translation3::new(0.0, 10.0, 0.0));
+
+        let r = Ray::new(Point3::new(0.0, 10.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 4.0);
+        // the normal follows too, even though the group moved after the sphere was added
+        let n = xs[0].object.normal_at(&Point3::new(0.0, 10.0, -1.0));
+        assert_relative_eq!(n, Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn bounds_of_children() {
+        let mut g = Group::new();
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(2.0, 5.0, -3.0) * Affine3::from_scale(Scale3::new(2.0, 2.0, 2.0)));
+        g.add_child(s);
+        let mut c = Cube::new();
+        c.set_transform(Translation3::new(-4.0, -1.0, 4.0) * Affine3::from_scale(Scale3::new(0.5, 1.0, 0.5)));
+        g.add_child(c);
+
+        let b = g.bounds();
+        assert_relative_eq!(b.min, Point3::new(-4.5, -2.0, -5.0));
+        assert_relative_eq!(b.max, Point3::new(4.0, 7.0, 4.5));
+    }
+
+    #[test]
+    fn bounds_unbounded_child() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        g.add_child(Plane::new());
+        assert!(!g.bounds().is_finite());
+    }
+
+    #[test]
+    fn ray_missing_bounds_misses_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::new());
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+        assert!(g.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn many_children() {
+        // A row of spheres, the ray only goes through the one at x = 6
+        let mut g = Group::new();
+        for i in 0..100 {
+            let mut s = Sphere::new();
+            s.set_transform(Translation3::new(i as f32 * 3.0, 0.0, 0.0));
+            g.add_child(s);
+        }
+        let r = Ray::new(Point3::new(6.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[0].object, g.children()[2].as_ref()));
+
+        // Adding a child after intersecting rebuilds the hierarchy
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(6.0, 0.0, 3.0));
+        g.add_child(s);
+        assert_eq!(g.intersect(&r).len(), 4);
+    }
+
+    #[test]
+    fn scaled_up_group() {
+        // Object space directions shrink as the group grows, the hierarchy mustn't take them for parallel
+        let mut g = Group::new();
+        for i in 0..20 {
+            let mut c = Cube::new();
+            c.set_transform(Translation3::new(i as f32 * 3.0, 0.0, 0.0));
+            g.add_child(c);
+        }
+        g.set_transform(Scale3::new(10000.0, 10000.0, 10000.0));
+        let r = Ray::new(Point3::new(0.5, 20000.0, -20000.0), Vector3::new(0.0, -0.5, 0.866));
+        let xs = g.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert!(std::ptr::addr_eq(xs[0].object, g.children()[0].as_ref()));
+        assert_relative_eq!(xs[0].t, 20000.0, max_relative = 1e-4);
+    }
+}
@@ -0,0 +1,133 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::EPSILON;
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// The infinite xz plane of its object space, facing +y.
+///
+/// Tilt and lift it with `Shape::set_transform` to make walls and floors.
+#[derive(Debug, Clone, Default)]
+pub struct Plane {
+    base: ShapeBase,
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Shape for Plane {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        // A ray parallel to the plane never meets it. A coplanar ray meets it everywhere,
+        // but the plane is infinitely thin so there is nothing to see either.
+        if ray.direction.y.abs() < EPSILON * ray.direction.norm() {
+            return Intersections::default();
+        }
+
+        let t = -ray.origin.y / ray.direction.y;
+        Intersections::new(vec![Intersection::new(t, self)])
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(
+            Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY),
+            Point3::new(f32::INFINITY, 0.0, f32::INFINITY),
+        )
+    }
+
+    fn local_normal_at(&self, _point: &Point3<f32>) -> Vector3<f32> {
+        Vector3::new(0.0, 1.0, 0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{Rotation3, Translation3};
+
+    #[test]
+    fn normal_is_constant() {
+        let p = Plane::new();
+        let up = Vector3::new(0.0, 1.0, 0.0);
+        assert_relative_eq!(p.local_normal_at(&Point3::new(0.0, 0.0, 0.0)), up);
+        assert_relative_eq!(p.local_normal_at(&Point3::new(10.0, 0.0, -10.0)), up);
+        assert_relative_eq!(p.local_normal_at(&Point3::new(-5.0, 0.0, 150.0)), up);
+    }
+
+    #[test]
+    fn parallel() {
+        let p = Plane::new();
+        let r = Ray::new(Point3::new(0.0, 10.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn coplanar() {
+        let p = Plane::new();
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        assert!(p.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 1.0);
+        assert!(std::ptr::addr_eq(xs[0].object, &p));
+    }
+
+    #[test]
+    fn from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Point3::new(0.0, -1.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let xs = p.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 1.0);
+    }
+
+    #[test]
+    fn wall() {
+        // Stand the plane up and push it back: a wall facing -z at z = 5
+        let mut p = Plane::new();
+        let r = Rotation3::from_axis_angle(&Vector3::x_axis(), -std::f32::consts::FRAC_PI_2);
+        p.set_transform(Translation3::new(0.0, 0.0, 5.0) * r);
+
+        let ray = Ray::new(Point3::new(0.0, 1.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = p.intersect(&ray);
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 5.0, epsilon = 1e-5);
+        assert_relative_eq!(p.normal_at(&Point3::new(0.0, 1.0, 5.0)), Vector3::new(0.0, 0.0, -1.0), epsilon = 1e-6);
+    }
+
+    #[test]
+    fn bounds() {
+        let b = Plane::new().bounds();
+        assert_eq!(b.min, Point3::new(f32::NEG_INFINITY, 0.0, f32::NEG_INFINITY));
+        assert_eq!(b.max, Point3::new(f32::INFINITY, 0.0, f32::INFINITY));
+    }
+
+    #[test]
+    fn scaled_into_a_floor() {
+        use nalgebra::Scale3;
+        // A ray onto a huge floor, its object space direction is below EPSILON on every axis
+        let mut p = Plane::new();
+        p.set_transform(Scale3::new(10000.0, 10000.0, 10000.0));
+        let xs = p.intersect(&Ray::new(Point3::new(0.0, 10000.0, 0.0), Vector3::new(0.0, -0.5, 0.866)));
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 20000.0, max_relative = 1e-4);
+    }
+}
@@ -0,0 +1,160 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::triangle::Face;
+use crate::shape::{Shape, ShapeBase};
+
+/// A triangle with a normal at each corner, blended across its surface.
+///
+/// Intersects exactly like a flat `Triangle`, but shading uses the corner normals weighted by
+/// the hit's `u` and `v`, so a mesh of them looks curved.
+#[derive(Debug, Clone)]
+pub struct SmoothTriangle {
+    base: ShapeBase,
+    face: Face,
+    n1: Vector3<f32>,
+    n2: Vector3<f32>,
+    n3: Vector3<f32>,
+}
+
+impl SmoothTriangle {
+    /// A triangle through `p1`, `p2` and `p3`, with the normals `n1`, `n2` and `n3` at those points
+    pub fn new(
+        p1: Point3<f32>,
+        p2: Point3<f32>,
+        p3: Point3<f32>,
+        n1: Vector3<f32>,
+        n2: Vector3<f32>,
+        n3: Vector3<f32>,
+    ) -> Self {
+        Self {
+            base: ShapeBase::new(),
+            face: Face::new(p1, p2, p3),
+            n1,
+            n2,
+            n3,
+        }
+    }
+
+    pub fn p1(&self) -> &Point3<f32> {
+        &self.face.p1
+    }
+
+    pub fn p2(&self) -> &Point3<f32> {
+        &self.face.p2
+    }
+
+    pub fn p3(&self) -> &Point3<f32> {
+        &self.face.p3
+    }
+
+    pub fn n1(&self) -> &Vector3<f32> {
+        &self.n1
+    }
+
+    pub fn n2(&self) -> &Vector3<f32> {
+        &self.n2
+    }
+
+    pub fn n3(&self) -> &Vector3<f32> {
+        &self.n3
+    }
+
+    fn interpolate(&self, u: f32, v: f32) -> Vector3<f32> {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        match self.face.intersect(ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::default(),
+        }
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.face.bounds()
+    }
+
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        // Without an intersection, work out where on the triangle the point is
+        let (u, v) = self.face.barycentric(point);
+        self.interpolate(u, v)
+    }
+
+    fn local_normal_at_hit(&self, _point: &Point3<f32>, hit: &Intersection<'_>) -> Vector3<f32> {
+        self.interpolate(hit.u, hit.v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn test_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(-1.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(-1.0, 0.0, 0.0),
+            Vector3::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn new() {
+        let t = test_triangle();
+        assert_relative_eq!(*t.p1(), Point3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(*t.p2(), Point3::new(-1.0, 0.0, 0.0));
+        assert_relative_eq!(*t.p3(), Point3::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(*t.n1(), Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(*t.n2(), Vector3::new(-1.0, 0.0, 0.0));
+        assert_relative_eq!(*t.n3(), Vector3::new(1.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn intersection_stores_uv() {
+        let t = test_triangle();
+        let r = Ray::new(Point3::new(-0.2, 0.3, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(&r);
+        assert_relative_eq!(xs[0].u, 0.45, epsilon = 1e-5);
+        assert_relative_eq!(xs[0].v, 0.25, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn interpolated_normal() {
+        let t = test_triangle();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+        let n = t.normal_at_hit(&Point3::new(0.0, 0.0, 0.0), &i);
+        assert_relative_eq!(n, Vector3::new(-0.5547, 0.83205, 0.0), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn normal_without_hit() {
+        let t = test_triangle();
+        let n = t.normal_at(&Point3::new(-0.2, 0.3, 0.0));
+        assert_relative_eq!(n, Vector3::new(-0.5547, 0.83205, 0.0), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn prepare_normal() {
+        let t = test_triangle();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+        let r = Ray::new(Point3::new(-0.2, 0.3, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = Intersections::new(vec![i]);
+        let comps = i.prepare_computations(&r, &xs);
+        assert_relative_eq!(comps.normalv, Vector3::new(-0.5547, 0.83205, 0.0), epsilon = 1e-4);
+    }
+}
@@ -0,0 +1,219 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// A unit sphere centered at the origin of its object space.
+///
+/// Move, stretch or squash it with `Shape::set_transform`.
+#[derive(Debug, Clone, Default)]
+pub struct Sphere {
+    base: ShapeBase,
+}
+
+impl Sphere {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Shape for Sphere {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        // Solve |origin + t*direction - center|^2 = 1 for t
+        let sphere_to_ray = ray.origin - Point3::origin();
+        let a = ray.direction.dot(&ray.direction);
+        let b = 2.0 * ray.direction.dot(&sphere_to_ray);
+        let c = sphere_to_ray.dot(&sphere_to_ray) - 1.0;
+
+        let discriminant = b * b - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return Intersections::default();
+        }
+
+        let sqrt_disc = discriminant.sqrt();
+        let t1 = (-b - sqrt_disc) / (2.0 * a);
+        let t2 = (-b + sqrt_disc) / (2.0 * a);
+        Intersections::new(vec![
+            Intersection::new(t1, self),
+            Intersection::new(t2, self),
+        ])
+    }
+
+    fn bounds(&self) -> Aabb {
+        Aabb::new(Point3::new(-1.0, -1.0, -1.0), Point3::new(1.0, 1.0, 1.0))
+    }
+
+    fn local_normal_at(&self, point: &Point3<f32>) -> Vector3<f32> {
+        point - Point3::origin()
+    }
+}
+
+/// A sphere made of clear glass, for refraction tests
+#[cfg(test)]
+pub(crate) fn glass_sphere() -> Sphere {
+    let mut s = Sphere::new();
+    s.material_mut().transparency = 1.0;
+    s.material_mut().refractive_index = 1.5;
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use nalgebra::{Affine3, Scale3, Translation3};
+    use crate::math::affine::Affine3Ext;
+
+    #[test]
+    fn two_points() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 4.0);
+        assert_relative_eq!(xs[1].t, 6.0);
+    }
+
+    #[test]
+    fn tangent() {
+        let r = Ray::new(Point3::new(0.0, 1.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 5.0);
+        assert_relative_eq!(xs[1].t, 5.0);
+    }
+
+    #[test]
+    fn miss() {
+        let r = Ray::new(Point3::new(0.0, 2.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn origin_inside() {
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, -1.0);
+        assert_relative_eq!(xs[1].t, 1.0);
+    }
+
+    #[test]
+    fn behind_ray() {
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, -6.0);
+        assert_relative_eq!(xs[1].t, -4.0);
+        assert!(xs.hit().is_none());
+    }
+
+    #[test]
+    fn sets_object() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let s = Sphere::new();
+        let xs = s.intersect(&r);
+        assert!(std::ptr::addr_eq(xs[0].object, &s));
+        assert!(std::ptr::addr_eq(xs[1].object, &s));
+    }
+
+    #[test]
+    fn scaled() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Scale3::new(2.0, 2.0, 2.0));
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 3.0);
+        assert_relative_eq!(xs[1].t, 7.0);
+    }
+
+    #[test]
+    fn translated() {
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        assert!(s.intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn chained() {
+
+        // Stretch along x, then slide along z: the ray now enters at z = 2 - 1 and leaves at z = 2 + 1
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(0.0, 0.0, 2.0) * Affine3::from_scale(Scale3::new(3.0, 1.0, 1.0)));
+        let xs = s.intersect(&r);
+        assert_eq!(xs.len(), 2);
+        assert_relative_eq!(xs[0].t, 6.0);
+        assert_relative_eq!(xs[1].t, 8.0);
+    }
+
+    #[test]
+    fn normal_on_axes() {
+        let s = Sphere::new();
+        assert_relative_eq!(s.normal_at(&Point3::new(1.0, 0.0, 0.0)), Vector3::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(s.normal_at(&Point3::new(0.0, 1.0, 0.0)), Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(s.normal_at(&Point3::new(0.0, 0.0, 1.0)), Vector3::new(0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn normal_is_normalized() {
+        let s = Sphere::new();
+        let k = f32::sqrt(3.0) / 3.0;
+        let n = s.normal_at(&Point3::new(k, k, k));
+        assert_relative_eq!(n, Vector3::new(k, k, k), epsilon = 1e-6);
+        assert_relative_eq!(n, n.normalize());
+    }
+
+    #[test]
+    fn normal_translated() {
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(0.0, 1.0, 0.0));
+        let k = std::f32::consts::FRAC_1_SQRT_2;
+        let n = s.normal_at(&Point3::new(0.0, 1.0 + k, -k));
+        assert_relative_eq!(n, Vector3::new(0.0, k, -k), epsilon = 1e-5);
+    }
+
+    #[test]
+    fn glass() {
+        let s = glass_sphere();
+        assert_relative_eq!(s.material().transparency, 1.0);
+        assert_relative_eq!(s.material().refractive_index, 1.5);
+    }
+
+    #[test]
+    fn default_transform() {
+        let s = Sphere::new();
+        assert_relative_eq!(s.transform().matrix(), Affine3::<f32>::identity().matrix());
+    }
+
+    #[test]
+    fn bounds() {
+        let b = Sphere::new().bounds();
+        assert_relative_eq!(b.min, Point3::new(-1.0, -1.0, -1.0));
+        assert_relative_eq!(b.max, Point3::new(1.0, 1.0, 1.0));
+    }
+
+    #[test]
+    fn parent_space_bounds() {
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(1.0, -3.0, 5.0) * Affine3::from_scale(Scale3::new(0.5, 2.0, 4.0)));
+        let b = s.parent_space_bounds();
+        assert_relative_eq!(b.min, Point3::new(0.5, -5.0, 1.0));
+        assert_relative_eq!(b.max, Point3::new(1.5, -1.0, 9.0));
+    }
+}
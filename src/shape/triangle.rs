@@ -0,0 +1,244 @@
+use nalgebra::{Point3, Vector3};
+use crate::intersection::{Intersection, Intersections};
+use crate::math::EPSILON;
+use crate::math::aabb::Aabb;
+use crate::math::ray::Ray;
+use crate::shape::{Shape, ShapeBase};
+
+/// A flat triangle between three points of its object space.
+///
+/// Its intersections carry the barycentric `u` and `v` of the hit: the point is
+/// `p1 + u * (p2 - p1) + v * (p3 - p1)`.
+#[derive(Debug, Clone)]
+pub struct Triangle {
+    base: ShapeBase,
+    face: Face,
+}
+
+impl Triangle {
+    pub fn new(p1: Point3<f32>, p2: Point3<f32>, p3: Point3<f32>) -> Self {
+        Self {
+            base: ShapeBase::new(),
+            face: Face::new(p1, p2, p3),
+        }
+    }
+
+    pub fn p1(&self) -> &Point3<f32> {
+        &self.face.p1
+    }
+
+    pub fn p2(&self) -> &Point3<f32> {
+        &self.face.p2
+    }
+
+    pub fn p3(&self) -> &Point3<f32> {
+        &self.face.p3
+    }
+
+    /// The edge from `p1` to `p2`
+    pub fn e1(&self) -> &Vector3<f32> {
+        &self.face.e1
+    }
+
+    /// The edge from `p1` to `p3`
+    pub fn e2(&self) -> &Vector3<f32> {
+        &self.face.e2
+    }
+
+    pub fn normal(&self) -> &Vector3<f32> {
+        &self.face.normal
+    }
+}
+
+impl Shape for Triangle {
+    fn base(&self) -> &ShapeBase {
+        &self.base
+    }
+
+    fn base_mut(&mut self) -> &mut ShapeBase {
+        &mut self.base
+    }
+
+    fn local_intersect(&self, ray: &Ray) -> Intersections<'_> {
+        match self.face.intersect(ray) {
+            Some((t, u, v)) => Intersections::new(vec![Intersection::with_uv(t, self, u, v)]),
+            None => Intersections::default(),
+        }
+    }
+
+    fn bounds(&self) -> Aabb {
+        self.face.bounds()
+    }
+
+    fn local_normal_at(&self, _point: &Point3<f32>) -> Vector3<f32> {
+        self.face.normal
+    }
+}
+
+/// The three corners of a triangle, with the edges and normal that every intersection needs
+/// worked out once up front. Shared by `Triangle` and `SmoothTriangle`.
+#[derive(Debug, Clone)]
+pub(super) struct Face {
+    pub(super) p1: Point3<f32>,
+    pub(super) p2: Point3<f32>,
+    pub(super) p3: Point3<f32>,
+    pub(super) e1: Vector3<f32>,
+    pub(super) e2: Vector3<f32>,
+    pub(super) normal: Vector3<f32>,
+    /// Length of `e2 x e1`, twice the triangle's area
+    area2: f32,
+}
+
+impl Face {
+    pub(super) fn new(p1: Point3<f32>, p2: Point3<f32>, p3: Point3<f32>) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let cross = e2.cross(&e1);
+        Self {
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal: cross.normalize(),
+            area2: cross.norm(),
+        }
+    }
+
+    /// Möller–Trumbore: the `t`, `u` and `v` where the ray goes through the triangle, if it does.
+    pub(super) fn intersect(&self, ray: &Ray) -> Option<(f32, f32, f32)> {
+        let dir_cross_e2 = ray.direction.cross(&self.e2);
+        let det = self.e1.dot(&dir_cross_e2);
+        // `det` is the cosine between the ray and the normal, scaled by the lengths of both.
+        // Scaling the tolerance the same way keeps tiny mesh triangles from all looking parallel.
+        if det.abs() < EPSILON * ray.direction.norm() * self.area2 {
+            return None;
+        }
+
+        let f = 1.0 / det;
+        let p1_to_origin = ray.origin - self.p1;
+        let u = f * p1_to_origin.dot(&dir_cross_e2);
+        if !(0.0..=1.0).contains(&u) {
+            return None;
+        }
+
+        let origin_cross_e1 = p1_to_origin.cross(&self.e1);
+        let v = f * ray.direction.dot(&origin_cross_e1);
+        if v < 0.0 || u + v > 1.0 {
+            return None;
+        }
+
+        let t = f * self.e2.dot(&origin_cross_e1);
+        Some((t, u, v))
+    }
+
+    /// The barycentric `u` and `v` of a point on the triangle
+    pub(super) fn barycentric(&self, point: &Point3<f32>) -> (f32, f32) {
+        let p = point - self.p1;
+        let (d11, d12, d22) = (self.e1.dot(&self.e1), self.e1.dot(&self.e2), self.e2.dot(&self.e2));
+        let (dp1, dp2) = (p.dot(&self.e1), p.dot(&self.e2));
+        let denom = d11 * d22 - d12 * d12;
+        ((d22 * dp1 - d12 * dp2) / denom, (d11 * dp2 - d12 * dp1) / denom)
+    }
+
+    pub(super) fn bounds(&self) -> Aabb {
+        let mut bounds = Aabb::empty();
+        for p in [&self.p1, &self.p2, &self.p3] {
+            bounds.add_point(p);
+        }
+        bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn test_triangle() -> Triangle {
+        Triangle::new(
+            Point3::new(0.0, 1.0, 0.0),
+            Point3::new(-1.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn new() {
+        let t = test_triangle();
+        assert_relative_eq!(*t.p1(), Point3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(*t.p2(), Point3::new(-1.0, 0.0, 0.0));
+        assert_relative_eq!(*t.p3(), Point3::new(1.0, 0.0, 0.0));
+        assert_relative_eq!(*t.e1(), Vector3::new(-1.0, -1.0, 0.0));
+        assert_relative_eq!(*t.e2(), Vector3::new(1.0, -1.0, 0.0));
+        assert_relative_eq!(*t.normal(), Vector3::new(0.0, 0.0, -1.0));
+    }
+
+    #[test]
+    fn normal_is_constant() {
+        let t = test_triangle();
+        assert_relative_eq!(t.local_normal_at(&Point3::new(0.0, 0.5, 0.0)), *t.normal());
+        assert_relative_eq!(t.local_normal_at(&Point3::new(-0.5, 0.75, 0.0)), *t.normal());
+        assert_relative_eq!(t.local_normal_at(&Point3::new(0.5, 0.25, 0.0)), *t.normal());
+    }
+
+    #[test]
+    fn parallel() {
+        let t = test_triangle();
+        let r = Ray::new(Point3::new(0.0, -1.0, -2.0), Vector3::new(0.0, 1.0, 0.0));
+        assert!(t.local_intersect(&r).is_empty());
+    }
+
+    #[test]
+    fn misses_edges() {
+        let t = test_triangle();
+        // Beyond p1-p3, p1-p2 and p2-p3
+        for origin in [Point3::new(1.0, 1.0, -2.0), Point3::new(-1.0, 1.0, -2.0), Point3::new(0.0, -1.0, -2.0)] {
+            let r = Ray::new(origin, Vector3::new(0.0, 0.0, 1.0));
+            assert!(t.local_intersect(&r).is_empty(), "{origin:?}");
+        }
+    }
+
+    #[test]
+    fn strikes() {
+        let t = test_triangle();
+        let r = Ray::new(Point3::new(0.0, 0.5, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(&r);
+        assert_eq!(xs.len(), 1);
+        assert_relative_eq!(xs[0].t, 2.0);
+        assert_relative_eq!(xs[0].u, 0.25);
+        assert_relative_eq!(xs[0].v, 0.25);
+    }
+
+    #[test]
+    fn small_triangle() {
+        // Far smaller than EPSILON on every side, but still not parallel to the ray
+        let t = Triangle::new(
+            Point3::new(0.0, 1e-3, 0.0),
+            Point3::new(-1e-3, 0.0, 0.0),
+            Point3::new(1e-3, 0.0, 0.0),
+        );
+        let r = Ray::new(Point3::new(0.0, 5e-4, -2.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_eq!(t.local_intersect(&r).len(), 1);
+    }
+
+    #[test]
+    fn barycentric() {
+        let t = test_triangle();
+        let (u, v) = t.face.barycentric(&Point3::new(-0.2, 0.3, 0.0));
+        assert_relative_eq!(u, 0.45, epsilon = 1e-6);
+        assert_relative_eq!(v, 0.25, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn bounds() {
+        let t = Triangle::new(
+            Point3::new(-3.0, 7.0, 2.0),
+            Point3::new(6.0, 2.0, -4.0),
+            Point3::new(2.0, -1.0, -1.0),
+        );
+        let b = t.bounds();
+        assert_relative_eq!(b.min, Point3::new(-3.0, -1.0, -4.0));
+        assert_relative_eq!(b.max, Point3::new(6.0, 7.0, 2.0));
+    }
+}
@@ -0,0 +1,771 @@
+use nalgebra::{Point3, Vector3};
+use crate::canvas::color::Color;
+use crate::intersection::{Computations, Intersections};
+use crate::light::{lighting, Light};
+use crate::math::ray::Ray;
+use crate::shape::Shape;
+
+/// How many times a ray may bounce off reflective surfaces, unless told otherwise
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// A scene: every object to render and the lights shining on them.
+#[derive(Debug)]
+pub struct World {
+    objects: Vec<Box<dyn Shape>>,
+    lights: Vec<Box<dyn Light>>,
+    max_depth: usize,
+}
+
+impl World {
+    /// An empty world, no objects and no lights
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            lights: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+        }
+    }
+
+    /// How many bounces `color_at` follows before giving up and returning black
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    pub fn add_object<S: Shape + 'static>(&mut self, object: S) {
+        self.objects.push(Box::new(object));
+    }
+
+    pub fn objects(&self) -> &[Box<dyn Shape>] {
+        &self.objects
+    }
+
+    pub fn objects_mut(&mut self) -> &mut [Box<dyn Shape>] {
+        &mut self.objects
+    }
+
+    pub fn add_light<L: Light + 'static>(&mut self, light: L) {
+        self.lights.push(Box::new(light));
+    }
+
+    pub fn lights(&self) -> &[Box<dyn Light>] {
+        &self.lights
+    }
+
+    pub fn lights_mut(&mut self) -> &mut [Box<dyn Light>] {
+        &mut self.lights
+    }
+
+    /// Intersect a ray with every object in the world, sorted by `t`
+    pub fn intersect(&self, ray: &Ray) -> Intersections<'_> {
+        self.objects
+            .iter()
+            .flat_map(|object| object.intersect(ray))
+            .collect()
+    }
+
+    /// The color at a prepared hit, with every light contributing.
+    ///
+    /// `remaining` is how many more bounces reflections may take.
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Color {
+        // Shade from the over point: from the point itself the shadow ray
+        // could hit the very surface it starts on
+        let surface = self.lights
+            .iter()
+            .map(|light| lighting(
+                comps.object.material(),
+                comps.object,
+                light.as_ref(),
+                &comps.over_point,
+                &comps.eyev,
+                &comps.normalv,
+                self.intensity_at(light.as_ref(), &comps.over_point),
+            ))
+            .fold(Color::new(0.0, 0.0, 0.0), |acc, c| acc + c);
+
+        let reflected = self.reflected_color(comps, remaining);
+        let refracted = self.refracted_color(comps, remaining);
+
+        // A surface that both mirrors and lets light through splits the light between the two,
+        // more of it is reflected the more grazing the angle
+        let material = comps.object.material();
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            surface + reflected * reflectance + refracted * (1.0 - reflectance)
+        } else {
+            surface + reflected + refracted
+        }
+    }
+
+    /// The color mirrored by a reflective surface, black once no bounces remain
+    pub fn reflected_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let reflective = comps.object.material().reflective;
+        if remaining == 0 || reflective == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
+        self.color_at_depth(&reflect_ray, remaining - 1) * reflective
+    }
+
+    /// The fraction of `light` that reaches `point`: the share of the light's samples
+    /// not hidden from it by a shadow. Always all or nothing for a light with a single sample.
+    pub fn intensity_at(&self, light: &dyn Light, point: &Point3<f32>) -> f32 {
+        let samples = light.samples(point);
+        if samples.is_empty() {
+            return 0.0;
+        }
+        let lit = samples
+            .iter()
+            .filter(|sample| !self.is_occluded(point, &sample.direction, sample.distance))
+            .count();
+        lit as f32 / samples.len() as f32
+    }
+
+    /// Is there anything casting a shadow between `point` and the light at `light_position`?
+    pub fn is_shadowed(&self, light_position: &Point3<f32>, point: &Point3<f32>) -> bool {
+        let v = light_position - point;
+        self.is_occluded(point, &v.normalize(), v.magnitude())
+    }
+
+    /// Does anything casting a shadow sit within `distance` of `point`, along the normalized `direction`?
+    fn is_occluded(&self, point: &Point3<f32>, direction: &Vector3<f32>, distance: f32) -> bool {
+        let ray = Ray::new(*point, *direction);
+
+        // Not just the first hit: a shadowless object in front can't hide one that casts a shadow
+        self.intersect(&ray)
+            .iter()
+            .any(|i| i.t >= 0.0 && i.t < distance && i.object.casts_shadow())
+    }
+
+    /// The color seen through a transparent surface, bent by Snell's law.
+    ///
+    /// Black once no bounces remain, or when the light is totally reflected inside the surface.
+    pub fn refracted_color(&self, comps: &Computations, remaining: usize) -> Color {
+        let transparency = comps.object.material().transparency;
+        if remaining == 0 || transparency == 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        // Snell's law: sin(theta_t) = n1/n2 * sin(theta_i)
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(&comps.normalv);
+        let sin2_t = n_ratio * n_ratio * (1.0 - cos_i * cos_i);
+        if sin2_t > 1.0 {
+            // Total internal reflection
+            return Color::new(0.0, 0.0, 0.0);
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        // Rays going into the surface start from below it
+        let refract_ray = Ray::new(comps.under_point, direction);
+        self.color_at_depth(&refract_ray, remaining - 1) * transparency
+    }
+
+    /// The color seen along a ray, black if it hits nothing
+    pub fn color_at(&self, ray: &Ray) -> Color {
+        self.color_at_depth(ray, self.max_depth)
+    }
+
+    /// `color_at`, with at most `remaining` more bounces.
+    ///
+    /// Every bounce uses one up, so even two facing mirrors terminate.
+    pub fn color_at_depth(&self, ray: &Ray, remaining: usize) -> Color {
+        let xs = self.intersect(ray);
+        match xs.hit() {
+            Some(hit) => self.shade_hit(&hit.prepare_computations(ray, &xs), remaining),
+            None => Color::new(0.0, 0.0, 0.0),
+        }
+    }
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The world most tests are written against: two concentric spheres lit from the upper left.
+#[cfg(test)]
+pub(crate) fn test_world() -> World {
+    use nalgebra::{Point3, Scale3};
+    use crate::light::PointLight;
+    use crate::material::Material;
+    use crate::shape::sphere::Sphere;
+
+    let mut world = World::new();
+    world.add_light(PointLight::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+
+    let mut s1 = Sphere::new();
+    s1.set_material(Material {
+        color: Color::new(0.8, 1.0, 0.6),
+        diffuse: 0.7,
+        specular: 0.2,
+        ..Material::default()
+    });
+    world.add_object(s1);
+
+    let mut s2 = Sphere::new();
+    s2.set_transform(Scale3::new(0.5, 0.5, 0.5));
+    world.add_object(s2);
+
+    world
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use std::f32::consts::FRAC_1_SQRT_2;
+    use nalgebra::{Affine3, Scale3, Translation3, Vector3};
+    use crate::intersection::Intersection;
+    use crate::light::PointLight;
+    use crate::light::area::AreaLight;
+    use crate::light::directional::DirectionalLight;
+    use crate::light::spot::SpotLight;
+    use crate::material::Material;
+    use crate::math::affine::Affine3Ext;
+    use crate::intersection::Intersections;
+    use crate::pattern::TestPattern;
+    use crate::shape::plane::Plane;
+    use crate::shape::sphere::{glass_sphere, Sphere};
+
+    #[test]
+    fn empty() {
+        let w = World::new();
+        assert!(w.objects().is_empty());
+        assert!(w.lights().is_empty());
+    }
+
+    #[test]
+    fn fixture() {
+        let w = test_world();
+        assert_eq!(w.objects().len(), 2);
+        assert_eq!(w.lights().len(), 1);
+        assert_relative_eq!(w.lights()[0].intensity(), Color::new(1.0, 1.0, 1.0));
+        assert_relative_eq!(w.objects()[0].material().color, Color::new(0.8, 1.0, 0.6));
+    }
+
+    #[test]
+    fn intersect() {
+        let w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(&r);
+        let ts: Vec<f32> = xs.iter().map(|i| i.t).collect();
+        assert_eq!(ts, vec![4.0, 4.5, 5.5, 6.0]);
+    }
+
+    #[test]
+    fn shade_outside() {
+        let w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].as_ref();
+        let i = Intersection::new(4.0, shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.38066, 0.47583, 0.2855), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn shade_inside() {
+        let mut w = test_world();
+        w.lights_mut()[0] = Box::new(PointLight::new(Point3::new(0.0, 0.25, 0.0), Color::new(1.0, 1.0, 1.0)));
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[1].as_ref();
+        let i = Intersection::new(0.5, shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.90498, 0.90498, 0.90498), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn shade_sums_lights() {
+        let mut w = test_world();
+        w.add_light(PointLight::new(Point3::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].as_ref();
+        let i = Intersection::new(4.0, shape);
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.76132, 0.95166, 0.5710), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn no_shadow_when_nothing_collinear() {
+        let w = test_world();
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        assert!(!w.is_shadowed(&light, &Point3::new(0.0, 10.0, 0.0)));
+    }
+
+    #[test]
+    fn shadow_when_object_between() {
+        let w = test_world();
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        assert!(w.is_shadowed(&light, &Point3::new(10.0, -10.0, 10.0)));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_light() {
+        let w = test_world();
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        assert!(!w.is_shadowed(&light, &Point3::new(-20.0, 20.0, -20.0)));
+    }
+
+    #[test]
+    fn no_shadow_when_object_behind_point() {
+        let w = test_world();
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        assert!(!w.is_shadowed(&light, &Point3::new(-2.0, 2.0, -2.0)));
+    }
+
+    #[test]
+    fn no_shadow_from_shadowless_objects() {
+        let mut w = test_world();
+        for object in w.objects_mut() {
+            object.set_casts_shadow(false);
+        }
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        assert!(!w.is_shadowed(&light, &Point3::new(10.0, -10.0, 10.0)));
+    }
+
+    #[test]
+    fn no_shadow_from_shadowless_groups() {
+        use crate::shape::csg::Csg;
+        use crate::shape::group::Group;
+
+        let light = Point3::new(-10.0, 10.0, -10.0);
+        let point = Point3::new(10.0, -10.0, 10.0);
+
+        // Turned off before and after the children are added, and inside another group
+        let mut before = Group::new();
+        before.set_casts_shadow(false);
+        before.add_child(Sphere::new());
+        let mut after = Group::new();
+        after.add_child(Sphere::new());
+        after.set_casts_shadow(false);
+        let mut outer = Group::new();
+        outer.add_child(after);
+        let mut csg = Csg::union(Sphere::new(), Sphere::new());
+        csg.set_casts_shadow(false);
+
+        let mut w = World::new();
+        w.add_object(before);
+        w.add_object(outer);
+        w.add_object(csg);
+        assert!(!w.is_shadowed(&light, &point));
+
+        // Turning them back on brings the shadows back
+        for object in w.objects_mut() {
+            object.set_casts_shadow(true);
+        }
+        assert!(w.is_shadowed(&light, &point));
+    }
+
+    #[test]
+    fn intensity_at_point_light() {
+        let w = test_world();
+        let light = w.lights()[0].as_ref();
+        let cases = [
+            (Point3::new(0.0, 1.0001, 0.0), 1.0),
+            (Point3::new(-1.0001, 0.0, 0.0), 1.0),
+            (Point3::new(0.0, 0.0, -1.0001), 1.0),
+            (Point3::new(0.0, 0.0, 1.0001), 0.0),
+            (Point3::new(1.0001, 0.0, 0.0), 0.0),
+            (Point3::new(0.0, -1.0001, 0.0), 0.0),
+            (Point3::new(0.0, 0.0, 0.0), 0.0),
+        ];
+        for (point, expected) in cases {
+            assert_relative_eq!(w.intensity_at(light, &point), expected);
+        }
+    }
+
+    #[test]
+    fn intensity_at_area_light() {
+        let mut w = test_world();
+        w.lights_mut()[0] = Box::new(AreaLight::new(
+            Point3::new(-0.5, -0.5, -5.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            2,
+            Vector3::new(0.0, 1.0, 0.0),
+            2,
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        let light = w.lights()[0].as_ref();
+        let cases = [
+            (Point3::new(0.0, 0.0, 2.0), 0.0),
+            (Point3::new(1.0, -1.0, 2.0), 0.25),
+            (Point3::new(1.5, 0.0, 2.0), 0.5),
+            (Point3::new(1.25, 1.25, 3.0), 0.75),
+            (Point3::new(0.0, 0.0, -2.0), 1.0),
+        ];
+        for (point, expected) in cases {
+            assert_relative_eq!(w.intensity_at(light, &point), expected);
+        }
+    }
+
+    #[test]
+    fn intensity_at_light_without_samples() {
+        // The step counts are public, so they can still be zeroed after `new` has checked them
+        let w = test_world();
+        let mut light = AreaLight::new(
+            Point3::new(-0.5, -0.5, -5.0),
+            Vector3::new(1.0, 0.0, 0.0),
+            2,
+            Vector3::new(0.0, 1.0, 0.0),
+            2,
+            Color::new(1.0, 1.0, 1.0),
+        );
+        light.usteps = 0;
+        assert_relative_eq!(w.intensity_at(&light, &Point3::new(0.0, 0.0, -2.0)), 0.0);
+    }
+
+    #[test]
+    fn directional_light_shadows_from_infinitely_far() {
+        let mut w = World::new();
+        w.add_light(DirectionalLight::new(Vector3::new(0.0, -1.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(0.0, 1000.0, 0.0));
+        w.add_object(s);
+
+        let light = w.lights()[0].as_ref();
+        assert_relative_eq!(w.intensity_at(light, &Point3::new(0.0, 0.0, 0.0)), 0.0);
+        assert_relative_eq!(w.intensity_at(light, &Point3::new(2.0, 0.0, 0.0)), 1.0);
+    }
+
+    #[test]
+    fn shade_sums_light_kinds() {
+        let shade_with = |light: Box<dyn Light>| {
+            let mut w = test_world();
+            w.lights_mut()[0] = light;
+            let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+            let i = Intersection::new(4.0, w.objects()[0].as_ref());
+            let comps = i.prepare_computations(&r, &[i]);
+            let color = w.shade_hit(&comps, DEFAULT_MAX_DEPTH);
+            (w, color)
+        };
+        let spot = SpotLight::new(
+            Point3::new(0.0, 0.0, -10.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.1,
+            0.2,
+            Color::new(1.0, 0.5, 0.5),
+        );
+        let sun = DirectionalLight::new(Vector3::new(1.0, -1.0, 1.0), Color::new(0.5, 0.5, 1.0));
+
+        let (_, spot_only) = shade_with(Box::new(spot));
+        let (mut w, sun_only) = shade_with(Box::new(sun));
+        w.add_light(spot);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects()[0].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), spot_only + sun_only, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn spot_light_only_lights_its_cone() {
+        let mut w = World::new();
+        w.add_light(SpotLight::new(
+            Point3::new(0.0, 0.0, -10.0),
+            Vector3::new(0.0, 0.0, 1.0),
+            0.1,
+            0.2,
+            Color::new(1.0, 1.0, 1.0),
+        ));
+        w.add_object(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_transform(Translation3::new(5.0, 0.0, 0.0));
+        w.add_object(s2);
+
+        // Facing the spot, but well outside its cone: ambient only
+        let r = Ray::new(Point3::new(5.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(w.color_at(&r), Color::new(0.1, 0.1, 0.1), epsilon = 1e-4);
+        // Straight down the spot's axis
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(w.color_at(&r), Color::new(1.9, 1.9, 1.9), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn soft_shadow_edge() {
+        // A sphere between a wide light and the floor: fully dark right under it, fully lit far
+        // away and somewhere in between at the edge of the shadow
+        let mut w = World::new();
+        w.add_light(AreaLight::new(
+            Point3::new(-2.0, 10.0, -2.0),
+            Vector3::new(4.0, 0.0, 0.0),
+            8,
+            Vector3::new(0.0, 0.0, 4.0),
+            8,
+            Color::new(1.0, 1.0, 1.0),
+        ).jittered());
+        let mut s = Sphere::new();
+        s.set_transform(Translation3::new(0.0, 5.0, 0.0) * Affine3::from_scale(Scale3::new(2.0, 2.0, 2.0)));
+        w.add_object(s);
+
+        let light = w.lights()[0].as_ref();
+        assert_relative_eq!(w.intensity_at(light, &Point3::new(0.0, 0.0, 0.0)), 0.0);
+        assert_relative_eq!(w.intensity_at(light, &Point3::new(10.0, 0.0, 0.0)), 1.0);
+        let penumbra = w.intensity_at(light, &Point3::new(4.0, 0.0, 0.0));
+        assert!(0.0 < penumbra && penumbra < 1.0, "{penumbra}");
+    }
+
+    #[test]
+    fn shade_in_shadow() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        w.add_object(Sphere::new());
+        let mut s2 = Sphere::new();
+        s2.set_transform(Translation3::new(0.0, 0.0, 10.0));
+        w.add_object(s2);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects()[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(0.1, 0.1, 0.1));
+    }
+
+    #[test]
+    fn shade_shadowless_occluder() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Point3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0)));
+        let mut helper = Sphere::new();
+        helper.set_casts_shadow(false);
+        w.add_object(helper);
+        let mut s2 = Sphere::new();
+        s2.set_transform(Translation3::new(0.0, 0.0, 10.0));
+        w.add_object(s2);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, 5.0), Vector3::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, w.objects()[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.shade_hit(&comps, DEFAULT_MAX_DEPTH), Color::new(1.9, 1.9, 1.9), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn color_miss() {
+        let w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 1.0, 0.0));
+        assert_relative_eq!(w.color_at(&r), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn color_hit() {
+        let w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        assert_relative_eq!(w.color_at(&r), Color::new(0.38066, 0.47583, 0.2855), epsilon = 1e-4);
+    }
+
+    #[test]
+    fn color_hit_behind_ray() {
+        let mut w = test_world();
+        for object in w.objects_mut() {
+            object.material_mut().ambient = 1.0;
+        }
+        let inner_color = w.objects()[1].material().color;
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.75), Vector3::new(0.0, 0.0, -1.0));
+        assert_relative_eq!(w.color_at(&r), inner_color);
+    }
+
+    fn with_mirror_floor(w: &mut World, reflective: f32) {
+        let mut floor = Plane::new();
+        floor.set_material(Material {
+            reflective,
+            ..Material::default()
+        });
+        floor.set_transform(Translation3::new(0.0, -1.0, 0.0));
+        w.add_object(floor);
+    }
+
+    #[test]
+    fn default_max_depth() {
+        let mut w = World::new();
+        assert_eq!(w.max_depth(), DEFAULT_MAX_DEPTH);
+        w.set_max_depth(2);
+        assert_eq!(w.max_depth(), 2);
+    }
+
+    #[test]
+    fn reflected_color_nonreflective() {
+        let mut w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 0.0, 1.0));
+        w.objects_mut()[1].material_mut().ambient = 1.0;
+        let i = Intersection::new(1.0, w.objects()[1].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.reflected_color(&comps, DEFAULT_MAX_DEPTH), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn reflected_color_reflective() {
+        let mut w = test_world();
+        with_mirror_floor(&mut w, 0.5);
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = Intersection::new(f32::sqrt(2.0), w.objects()[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(
+            w.reflected_color(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.19032, 0.2379, 0.14274),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn shade_hit_reflective() {
+        let mut w = test_world();
+        with_mirror_floor(&mut w, 0.5);
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = Intersection::new(f32::sqrt(2.0), w.objects()[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(
+            w.shade_hit(&comps, DEFAULT_MAX_DEPTH),
+            Color::new(0.87677, 0.92436, 0.82918),
+            epsilon = 1e-3
+        );
+    }
+
+    #[test]
+    fn reflected_color_at_max_depth() {
+        let mut w = test_world();
+        with_mirror_floor(&mut w, 0.5);
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let i = Intersection::new(f32::sqrt(2.0), w.objects()[2].as_ref());
+        let comps = i.prepare_computations(&r, &[i]);
+        assert_relative_eq!(w.reflected_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn parallel_mirrors_terminate() {
+        let mut w = World::new();
+        w.add_light(PointLight::new(Point3::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0)));
+
+        let mut lower = Plane::new();
+        lower.material_mut().reflective = 1.0;
+        lower.set_transform(Translation3::new(0.0, -1.0, 0.0));
+        w.add_object(lower);
+
+        let mut upper = Plane::new();
+        upper.material_mut().reflective = 1.0;
+        upper.set_transform(Translation3::new(0.0, 1.0, 0.0));
+        w.add_object(upper);
+
+        // Would recurse forever without the depth limit
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.0), Vector3::new(0.0, 1.0, 0.0));
+        let bounced = w.color_at(&r);
+
+        // and every bounce does add something
+        w.set_max_depth(0);
+        let direct = w.color_at(&r);
+        assert!(direct != bounced);
+    }
+
+    #[test]
+    fn refracted_color_opaque() {
+        let w = test_world();
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].as_ref();
+        let xs = Intersections::new(vec![Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_at_max_depth() {
+        let mut w = test_world();
+        w.objects_mut()[0].material_mut().transparency = 1.0;
+        w.objects_mut()[0].material_mut().refractive_index = 1.5;
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let shape = w.objects()[0].as_ref();
+        let xs = Intersections::new(vec![Intersection::new(4.0, shape), Intersection::new(6.0, shape)]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.refracted_color(&comps, 0), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_total_internal_reflection() {
+        let mut w = test_world();
+        w.objects_mut()[0].material_mut().transparency = 1.0;
+        w.objects_mut()[0].material_mut().refractive_index = 1.5;
+        let r = Ray::new(Point3::new(0.0, 0.0, FRAC_1_SQRT_2), Vector3::new(0.0, 1.0, 0.0));
+        let shape = w.objects()[0].as_ref();
+        let xs = Intersections::new(vec![
+            Intersection::new(-FRAC_1_SQRT_2, shape),
+            Intersection::new(FRAC_1_SQRT_2, shape),
+        ]);
+        // Inside the sphere, so look at the second intersection
+        let comps = xs[1].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn refracted_color_refracted_ray() {
+        let mut w = test_world();
+        w.objects_mut()[0].material_mut().ambient = 1.0;
+        w.objects_mut()[0].material_mut().set_pattern(TestPattern::default());
+        w.objects_mut()[1].material_mut().transparency = 1.0;
+        w.objects_mut()[1].material_mut().refractive_index = 1.5;
+        let r = Ray::new(Point3::new(0.0, 0.0, 0.1), Vector3::new(0.0, 1.0, 0.0));
+        let a = w.objects()[0].as_ref();
+        let b = w.objects()[1].as_ref();
+        let xs = Intersections::new(vec![
+            Intersection::new(-0.9899, a),
+            Intersection::new(-0.4899, b),
+            Intersection::new(0.4899, b),
+            Intersection::new(0.9899, a),
+        ]);
+        let comps = xs[2].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.refracted_color(&comps, 5), Color::new(0.0, 0.99888, 0.04725), epsilon = 1e-3);
+    }
+
+    #[test]
+    fn shade_hit_transparent() {
+        let mut w = test_world();
+        let mut floor = Plane::new();
+        floor.set_transform(Translation3::new(0.0, -1.0, 0.0));
+        floor.material_mut().transparency = 0.5;
+        floor.material_mut().refractive_index = 1.5;
+        w.add_object(floor);
+        let mut ball = Sphere::new();
+        ball.material_mut().color = Color::new(1.0, 0.0, 0.0);
+        ball.material_mut().ambient = 0.5;
+        ball.set_transform(Translation3::new(0.0, -3.5, -0.5));
+        w.add_object(ball);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let xs = Intersections::new(vec![Intersection::new(f32::sqrt(2.0), w.objects()[2].as_ref())]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.shade_hit(&comps, 5), Color::new(0.93642, 0.68642, 0.68642), epsilon = 1e-3);
+    }
+
+    #[test]
+    fn shade_hit_reflective_transparent() {
+        let mut w = test_world();
+        let mut floor = Plane::new();
+        floor.set_transform(Translation3::new(0.0, -1.0, 0.0));
+        floor.material_mut().reflective = 0.5;
+        floor.material_mut().transparency = 0.5;
+        floor.material_mut().refractive_index = 1.5;
+        w.add_object(floor);
+        let mut ball = Sphere::new();
+        ball.material_mut().color = Color::new(1.0, 0.0, 0.0);
+        ball.material_mut().ambient = 0.5;
+        ball.set_transform(Translation3::new(0.0, -3.5, -0.5));
+        w.add_object(ball);
+
+        let r = Ray::new(Point3::new(0.0, 0.0, -3.0), Vector3::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+        let xs = Intersections::new(vec![Intersection::new(f32::sqrt(2.0), w.objects()[2].as_ref())]);
+        let comps = xs[0].prepare_computations(&r, &xs);
+        assert_relative_eq!(w.shade_hit(&comps, 5), Color::new(0.93391, 0.69643, 0.69243), epsilon = 1e-3);
+    }
+
+    #[test]
+    fn glass_in_world() {
+        // A glass ball in front of the test world: looking through it still ends on the spheres behind.
+        // The lens's own faces add a grey sheen on top of the outer sphere's green.
+        let mut w = test_world();
+        let mut lens = glass_sphere();
+        lens.set_transform(Translation3::new(0.0, 0.0, -3.0));
+        w.add_object(lens);
+        let r = Ray::new(Point3::new(0.0, 0.0, -5.0), Vector3::new(0.0, 0.0, 1.0));
+        let c = w.color_at(&r);
+        assert_relative_eq!(c, Color::new(0.93216, 1.02733, 0.83700), epsilon = 1e-4);
+    }
+}